@@ -0,0 +1,108 @@
+//! Compares `CalculatedAttempt::from_attempt` with `compact::score` on random
+//! answer/guess pairs of the default word list, in every mode with and without
+//! fuzzy matching. Run with `cargo bench`.
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use handle::compact::{self, CompactRules, Interner, MAX_LENGTH};
+use handle::config::DictMode;
+use handle::constant::IDIOM_LENGTH;
+use handle::dict::{Dictionary, ValidationReport};
+use handle::evaluator::Mode;
+use handle::model::{Attempt, CalculatedAttempt, Rules};
+use rand::RngExt;
+use std::hint::black_box;
+
+const PAIRS: usize = 1000;
+
+fn scoring(c: &mut Criterion) {
+    let mut report = ValidationReport::default();
+    let dictionary =
+        Dictionary::load(DictMode::Degraded, &mut report).expect("failed to load the dictionary");
+    let list = dictionary.list(None).expect("no default list");
+    let length = list.length.unwrap_or(IDIOM_LENGTH);
+    assert!(
+        length <= MAX_LENGTH,
+        "{length}-character words do not fit in a compact word"
+    );
+
+    let words = list
+        .answers
+        .iter()
+        .filter_map(|answer| {
+            let attempt = Attempt::from(answer);
+            let characters = attempt.to_characters(length, list.scheme).ok()?;
+            Some((attempt, characters))
+        })
+        .collect::<Vec<_>>();
+    assert!(
+        words.len() >= 2,
+        "not enough {length}-character answers to benchmark"
+    );
+
+    let mut rng = rand::rng();
+    let pairs = (0..PAIRS)
+        .map(|_| {
+            (
+                rng.random_range(0..words.len()),
+                rng.random_range(0..words.len()),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let modes = [
+        Mode::Standard,
+        Mode::Pronunciation,
+        Mode::Toneless,
+        Mode::Classic,
+        Mode::Consuming,
+    ];
+    let mut group = c.benchmark_group("score");
+    for (mode, fuzzy) in modes
+        .into_iter()
+        .flat_map(|mode| [(mode, false), (mode, true)])
+    {
+        let rules = Rules {
+            fuzzy,
+            mode,
+            ..Default::default()
+        };
+        let options = rules.options(list.scheme, &dictionary.fuzzy);
+        let name = format!("{mode:?}{}", if fuzzy { " + fuzzy" } else { "" });
+
+        group.bench_with_input(
+            BenchmarkId::new("from_attempt", &name),
+            &pairs,
+            |b, pairs| {
+                b.iter(|| {
+                    for &(answer, guess) in pairs {
+                        let _ = black_box(CalculatedAttempt::from_attempt(
+                            &words[answer].1,
+                            &words[guess].0,
+                            &options,
+                        ));
+                    }
+                })
+            },
+        );
+
+        let mut interner = Interner::default();
+        let rules = CompactRules::new(&options, &mut interner);
+        let encoded = words
+            .iter()
+            .map(|(_, characters)| interner.encode(characters, rules.sandhi))
+            .collect::<Option<Vec<_>>>()
+            .expect("every word fits in a compact word");
+
+        group.bench_with_input(BenchmarkId::new("compact", &name), &pairs, |b, pairs| {
+            b.iter(|| {
+                for &(answer, guess) in pairs {
+                    black_box(compact::score(&encoded[answer], &encoded[guess], &rules));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, scoring);
+criterion_main!(benches);
@@ -0,0 +1,91 @@
+#{
+  /// ====== Config ======
+  let fonts = (
+    serif: ("Libertinus Serif", "LXGW Neo ZhiSong"),
+    mono: "Fira Code Retina",
+  )
+
+  let correct-color = rgb("#1d9c9c")
+  let fg-color = rgb("#5f6672")
+  let missing-color = rgb("#b5b8be")
+
+  /// ====== Styles ======
+
+  set text(font: fonts.serif, fill: fg-color)
+
+  set page(
+    height: auto,
+    width: auto,
+    margin: (left: 1em, right: 1em, top: 1.5em, bottom: .75em),
+    header: align(right, text(size: .65em, font: fonts.mono, fill: gray)[\@fa_555 Handle Bot]),
+  )
+
+  /// ====== Data ======
+
+  let json-path = sys.inputs.at("path", default: "./mock-leaderboard.json")
+  let data = json(json-path)
+
+  let period-label = (
+    "day": "today",
+    "week": "this week",
+    "month": "this month",
+    "all": "all time",
+  ).at(data.period)
+
+  let format-time(ms) = if ms == none {
+    "-"
+  } else {
+    let seconds = calc.floor(ms / 1000)
+    let rest = calc.rem(seconds, 60)
+    let padding = if rest < 10 { "0" } else { "" }
+    str(calc.floor(seconds / 60)) + ":" + padding + str(rest)
+  }
+
+  let format-average(average) = if average == none { "-" } else { str(calc.round(average, digits: 1)) }
+
+  // 排序所依据的一栏高亮显示
+  let columns = (
+    ("wins", "wins", entry => str(entry.wins)),
+    ("average_attempts", "avg. attempts", entry => format-average(entry.average_attempts)),
+    ("fastest", "fastest", entry => format-time(entry.fastest_ms)),
+    ("longest_streak", "best streak", entry => str(entry.longest_streak)),
+  )
+
+  /// ====== Content ======
+
+  align(center, text(size: 1.2em, period-label))
+
+  v(.5em)
+
+  if data.entries.len() == 0 {
+    align(center, text(fill: missing-color, "no games yet"))
+  } else {
+    grid(
+      columns: 2 + columns.len(),
+      column-gutter: 1.5em,
+      row-gutter: .75em,
+      align: (right, left) + (center,) * columns.len(),
+
+      text(size: .8em, fill: missing-color, "#"),
+      [],
+      ..columns.map(((key, label, _)) => text(
+        size: .8em,
+        fill: if key == data.metric { correct-color } else { missing-color },
+        label,
+      )),
+
+      ..data
+        .entries
+        .map(entry => (
+          text(font: fonts.mono, fill: if entry.rank == 1 { correct-color } else { fg-color }, str(entry.rank)),
+          text(size: 1.2em, if entry.name == none { entry.id } else { entry.name }),
+          ..columns.map(((key, _, value)) => text(
+            font: fonts.mono,
+            fill: if key == data.metric { correct-color } else { fg-color },
+            value(entry),
+          )),
+        ))
+        .flatten(),
+    )
+  }
+}
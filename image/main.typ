@@ -7,6 +7,7 @@
 
   let correct-color = rgb("#1d9c9c")
   let misplaced-color = rgb("#de7525")
+  let near-color = rgb("#c9a227")
   let bg-color = rgb("#f7f8fa")
   let fg-color = rgb("#5f6672")
   let missing-color = rgb("#b5b8be")
@@ -45,12 +46,14 @@
   let State = (
     missing: "Missing",
     misplaced: "Misplaced",
+    near: "Near",
     correct: "Correct",
   )
 
   let reverse-state-map = (
     "Missing": State.missing,
     "Misplaced": State.misplaced,
+    "Near": State.near,
     "Correct": State.correct,
   )
 
@@ -62,6 +65,13 @@
       "Rising": "2",
       "Low": "3",
       "Falling": "4",
+      "Neutral": "",
+      "HighLevel": "1",
+      "HighRising": "2",
+      "MidLevel": "3",
+      "LowFalling": "4",
+      "LowRising": "5",
+      "LowLevel": "6",
     ).at(tone)
   }
 
@@ -72,6 +82,12 @@
 
   // repr(data)
 
+  let guesser-name = guesser => if guesser.at("name", default: none) == none {
+    guesser.id
+  } else {
+    guesser.name
+  }
+
   let underlined(color, it) = if color == none {
     it
   } else {
@@ -126,23 +142,28 @@
         h(.1em)
 
         // 声调
-        let tone-str = tone-to-str(tone)
-        if tone-str == none {
+        if tone == none or tone == "" {
           h(.5em)
         } else {
-          text(size: .75em, baseline: -.5em, fill: tone-color, tone-str)
+          text(size: .75em, baseline: -.5em, fill: tone-color, tone)
         }
       },
       text(2em, fill: literal-color, literal),
     )
   }
 
-  let rows = data.result.map(it => {
+  let make-row = it => {
     it.characters.map(it => make-cell(
       it.literal,
-      it.pinyin.initial,
-      it.pinyin.vowel,
-      it.pinyin.tone,
+      ..{
+        // 注音模式下以注音代替拼音
+        let zhuyin = it.at("zhuyin", default: none)
+        if zhuyin == none {
+          (it.pinyin.initial, it.pinyin.vowel, tone-to-str(it.pinyin.tone))
+        } else {
+          (zhuyin.initial, zhuyin.vowel, zhuyin.tone)
+        }
+      },
       whole-color: if it.result.whole == "Correct" {
         correct-color
       } else {
@@ -162,6 +183,8 @@
         correct-color
       } else if it.result.initial == "Misplaced" {
         misplaced-color
+      } else if it.result.initial == "Near" {
+        near-color
       } else {
         missing-color
       },
@@ -169,6 +192,8 @@
         correct-color
       } else if it.result.vowel == "Misplaced" {
         misplaced-color
+      } else if it.result.vowel == "Near" {
+        near-color
       } else {
         missing-color
       },
@@ -182,19 +207,37 @@
     ))
 
     (
-      if it.verified {
-        align(
-          center + horizon,
-          check(),
-        )
+      {
+        let guesser = it.at("guesser", default: none)
+        if guesser == none {
+          if it.verified {
+            align(
+              center + horizon,
+              check(),
+            )
+          }
+        } else {
+          // 共享棋盘：标注猜测者
+          align(
+            left + horizon,
+            stack(
+              dir: ltr,
+              spacing: .5em,
+              if it.verified { check() } else { h(1em) },
+              text(size: .8em, font: fonts.mono, fill: fg-color, guesser-name(guesser)),
+            ),
+          )
+        }
       },
     )
-  })
+  }
+
+  let rows = data.result.map(make-row)
 
   /// ====== Content ======
 
   grid(
-    columns: 5,
+    columns: data.word_length + 1,
     rows: calc.min(
       data.max_attempt_count,
       data.result.len() + if data.finished { 0 } else { 1 },
@@ -213,7 +256,7 @@
             fill: bg-color,
           ),
         )
-          * 4
+          * data.word_length
       )
     },
   )
@@ -223,4 +266,46 @@
     " / "
     str(data.max_attempt_count)
   })
+
+  // 共享棋盘：记下猜中答案的人
+  let solver = data.at("solver", default: none)
+  if solver != none {
+    align(center, text(fill: correct-color, "✓ " + guesser-name(solver)))
+  }
+
+  // 没猜中时在下方给出答案
+  let answer-row = data.at("answer_row", default: none)
+  if answer-row != none {
+    v(.5em)
+    grid(
+      columns: data.word_length + 1,
+      gutter: .5em,
+      ..make-row(answer-row).flatten(),
+    )
+  }
+
+  // 结束后揭晓答案：成语、拼音与释义
+  let reveal = data.at("reveal", default: none)
+  if reveal != none {
+    v(.5em)
+    block(
+      width: (cell-size + .5em) * data.word_length,
+      fill: bg-color,
+      inset: 1em,
+      radius: .25em,
+      stack(
+        dir: ttb,
+        spacing: .75em,
+        text(size: 1.5em, reveal.word),
+        text(size: .9em, font: fonts.mono, reveal.pinyin),
+        if reveal.explanation != "" {
+          text(size: .9em, reveal.explanation)
+        },
+        // 出处与例句，数据里有才显示
+        ..("derivation", "example")
+          .filter(key => reveal.at(key, default: none) != none)
+          .map(key => text(size: .8em, fill: fg-color, reveal.at(key))),
+      ),
+    )
+  }
 }
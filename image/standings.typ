@@ -0,0 +1,101 @@
+#{
+  /// ====== Config ======
+  let fonts = (
+    serif: ("Libertinus Serif", "LXGW Neo ZhiSong"),
+    mono: "Fira Code Retina",
+  )
+
+  let correct-color = rgb("#1d9c9c")
+  let misplaced-color = rgb("#de7525")
+  let near-color = rgb("#c9a227")
+  let bg-color = rgb("#f7f8fa")
+  let fg-color = rgb("#5f6672")
+  let missing-color = rgb("#b5b8be")
+
+  let dot-size = .8em
+
+  /// ====== Styles ======
+
+  set text(font: fonts.serif, fill: fg-color)
+
+  set page(
+    height: auto,
+    width: auto,
+    margin: (left: 1em, right: 1em, top: 1.5em, bottom: .75em),
+    header: align(right, text(size: .65em, font: fonts.mono, fill: gray)[\@fa_555 Handle Bot]),
+  )
+
+  /// ====== Data ======
+
+  let json-path = sys.inputs.at("path", default: "./mock-standings.json")
+  let data = json(json-path)
+
+  let state-color = state => (
+    "Correct": correct-color,
+    "Misplaced": misplaced-color,
+    "Near": near-color,
+    "Missing": missing-color,
+  ).at(state)
+
+  // 每位玩家的棋盘缩略图，只显示整字的状态
+  let mini-board(rows) = if rows.len() == 0 {
+    text(fill: missing-color, "-")
+  } else {
+    stack(
+      dir: ttb,
+      spacing: .15em,
+      ..rows.map(row => stack(
+        dir: ltr,
+        spacing: .15em,
+        ..row.map(state => box(width: dot-size, height: dot-size, radius: .1em, fill: state-color(state))),
+      )),
+    )
+  }
+
+  let format-time(ms) = if ms == none {
+    "-"
+  } else {
+    let seconds = calc.floor(ms / 1000)
+    let rest = calc.rem(seconds, 60)
+    let padding = if rest < 10 { "0" } else { "" }
+    str(calc.floor(seconds / 60)) + ":" + padding + str(rest)
+  }
+
+  /// ====== Content ======
+
+  if data.answer != none {
+    align(center, stack(
+      dir: ttb,
+      spacing: .5em,
+      text(size: .9em, font: fonts.mono, data.answer.pinyin),
+      text(size: 2em, data.answer.word),
+    ))
+    v(.5em)
+  }
+
+  grid(
+    columns: 5,
+    column-gutter: 1.5em,
+    row-gutter: .75em,
+    align: horizon,
+
+    ..data
+      .players
+      .map(player => (
+        text(
+          font: fonts.mono,
+          fill: if player.rank == 1 { correct-color } else { fg-color },
+          if player.rank == none { "-" } else { str(player.rank) },
+        ),
+        text(size: 1.2em, player.name),
+        mini-board(player.rows),
+        text(font: fonts.mono, str(player.attempts) + " / " + str(data.max_attempt_count)),
+        text(font: fonts.mono, format-time(player.time_ms)),
+      ))
+      .flatten(),
+  )
+
+  if not data.finished {
+    align(center, text(size: .8em, fill: missing-color, "…"))
+  }
+}
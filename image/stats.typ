@@ -0,0 +1,84 @@
+#{
+  /// ====== Config ======
+  let fonts = (
+    serif: ("Libertinus Serif", "LXGW Neo ZhiSong"),
+    mono: "Fira Code Retina",
+  )
+
+  let correct-color = rgb("#1d9c9c")
+  let bg-color = rgb("#f7f8fa")
+  let fg-color = rgb("#5f6672")
+  let missing-color = rgb("#b5b8be")
+
+  let bar-width = 16em
+
+  /// ====== Styles ======
+
+  set text(font: fonts.serif, fill: fg-color)
+
+  set page(
+    height: auto,
+    width: auto,
+    margin: (left: 1em, right: 1em, top: 1.5em, bottom: .75em),
+    header: align(right, text(size: .65em, font: fonts.mono, fill: gray)[\@fa_555 Handle Bot]),
+  )
+
+  /// ====== Data ======
+
+  let json-path = sys.inputs.at("path", default: "./mock-stats.json")
+  let data = json(json-path)
+
+  let name = if data.name == none { data.id } else { data.name }
+
+  let figure(value, label) = stack(
+    dir: ttb,
+    spacing: .5em,
+    text(size: 2em, font: fonts.mono, value),
+    text(size: .8em, fill: missing-color, label),
+  )
+
+  /// ====== Content ======
+
+  align(center, text(size: 1.5em, name))
+
+  v(.5em)
+
+  grid(
+    columns: 4,
+    column-gutter: 2em,
+    align: center,
+    figure(str(data.played), "played"),
+    figure(str(calc.round(data.win_rate * 100)) + "%", "won"),
+    figure(str(data.current_streak), "streak"),
+    figure(str(data.best_streak), "best streak"),
+  )
+
+  v(1em)
+
+  // 猜中所用次数的分布，最多的一栏画满
+  let most = calc.max(1, ..data.distribution)
+  grid(
+    columns: 2,
+    column-gutter: .75em,
+    row-gutter: .4em,
+    align: (right + horizon, left + horizon),
+    ..data
+      .distribution
+      .enumerate()
+      .map(((i, count)) => (
+        text(font: fonts.mono, str(i + 1)),
+        box(
+          width: calc.max(1.5em, bar-width * count / most),
+          height: 1.2em,
+          fill: if count == 0 { bg-color } else { correct-color },
+          inset: (right: .4em),
+          align(right + horizon, text(size: .8em, font: fonts.mono, fill: if count == 0 {
+            missing-color
+          } else {
+            white
+          }, str(count))),
+        ),
+      ))
+      .flatten(),
+  )
+}
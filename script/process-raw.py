@@ -25,17 +25,24 @@ raw data:
     ...
 ]
 
-expected output:
+expected output (`derivation`, `example` and `abbreviation` are left out when
+missing):
 [
     {
         "explanation": "阿鼻梵语的译音，意译为无间”，即痛苦无有间断之意。常用来比喻黑暗的社会和严酷的牢狱。又比喻无法摆脱的极其痛苦的境地。",
         "pinyin": "a1 bi2 di4 yu4",
         "word": "阿鼻地狱",
+        "derivation": "语出《法华经·法师功德品》下至阿鼻地狱。”",
+        "example": "但也有少数意志薄弱的……逐步上当，终至堕入～。★《上饶集中营·炼狱杂记》",
+        "abbreviation": "abdy"
     },
     {
         "explanation": "指相互勾结，相互偏袒，结党营私。",
         "pinyin": "e1 dang3 bi3 zhou1",
         "word": "阿党比周",
+        "derivation": "三国·魏·曹操《整齐风俗令》阿党比周，先圣所疾也。”",
+        "example": "《论语·卫灵公》众恶之，必察焉；众好之，必察焉”何晏集解引三国魏王肃曰或众～，或其人特立不群，故好恶不可不察也。”",
+        "abbreviation": "edbz"
     },
     ...
 ]
@@ -81,13 +88,22 @@ def standardize_pinyins(pinyins):
     return ' '.join(map(standardize_pinyin, pinyins.split(' ')))
 
 
+# optional fields, `无` marks them missing in the raw data
+OPTIONAL_FIELDS = ('derivation', 'example', 'abbreviation')
+
+
 def process_raw_data(raw_data):
     def mapper(item):
-        return {
+        entry = {
             'word': item['word'],
             'pinyin': standardize_pinyins(item['pinyin']),
             'explanation': item['explanation']
         }
+        for field in OPTIONAL_FIELDS:
+            value = item.get(field, '').strip()
+            if value and value != '无':
+                entry[field] = value
+        return entry
 
     return list(map(mapper, filter(lambda item: len(item['word']) == 4, raw_data)))
 
@@ -0,0 +1,183 @@
+//! Importer for CC-CEDICT formatted word lists:
+//!
+//! ```text
+//! # comment
+//! 堅定不移 坚定不移 [jian1 ding4 bu4 yi2] /firm and unswerving/unswerving/
+//! ```
+
+use crate::model::Answer;
+use std::collections::{HashMap, HashSet};
+
+/// One line of a CC-CEDICT file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    /// Headword in traditional characters.
+    pub traditional: String,
+    /// Headword in simplified characters.
+    pub simplified: String,
+    /// Numbered-tone reading, as written in the file.
+    pub pinyin: String,
+    /// The `/`-separated glosses, in order.
+    pub definitions: Vec<String>,
+}
+
+/// Converts one CC-CEDICT syllable (`lu:4`, `ma5`, `Zhong1`) into the
+/// numbered-tone format used by `data/` (`lü4`, `ma`, `zhong1`).
+fn standardize_syllable(syllable: &str) -> String {
+    let syllable = syllable.to_lowercase().replace("u:", "ü");
+
+    match syllable.strip_suffix('5') {
+        Some(neutral) => neutral.to_string(),
+        None => syllable,
+    }
+}
+
+/// Parses a single line, returning `None` for comments, blank lines and lines
+/// that do not follow the `TRAD SIMP [PIN YIN] /DEF/` layout.
+pub fn parse_line(line: &str) -> Option<Entry> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (traditional, rest) = line.split_once(' ')?;
+    let (simplified, rest) = rest.split_once(' ')?;
+    let rest = rest.strip_prefix('[')?;
+    let (pinyin, rest) = rest.split_once(']')?;
+
+    let pinyin = pinyin
+        .split_whitespace()
+        .map(standardize_syllable)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let definitions = rest
+        .trim()
+        .trim_matches('/')
+        .split('/')
+        .filter(|def| !def.is_empty())
+        .map(ToString::to_string)
+        .collect();
+
+    Some(Entry {
+        traditional: traditional.to_string(),
+        simplified: simplified.to_string(),
+        pinyin,
+        definitions,
+    })
+}
+
+impl Entry {
+    /// Whether the word is made of `length` Han characters with one pinyin
+    /// syllable each, i.e. the entry can go through `TryFrom<&str> for Pinyin`.
+    fn is_consistent(&self, length: usize) -> bool {
+        let syllables = self.pinyin.split_whitespace().collect::<Vec<_>>();
+
+        self.simplified.chars().count() == length
+            && self
+                .simplified
+                .chars()
+                .all(|c| c.is_alphabetic() && !c.is_ascii())
+            && syllables.len() == length
+            && syllables
+                .iter()
+                .all(|s| s.chars().all(|c| c.is_ascii_alphanumeric() || c == 'ü'))
+    }
+}
+
+impl From<Entry> for Answer {
+    fn from(entry: Entry) -> Self {
+        Self {
+            word: entry.simplified,
+            pinyin: entry.pinyin,
+            explanation: entry.definitions.join("; "),
+            derivation: None,
+            example: None,
+            abbreviation: None,
+        }
+    }
+}
+
+/// Turns a CC-CEDICT file into answers of exactly `length` characters.
+///
+/// Only the first reading of each simplified word is kept. With a `frequency`
+/// list, words missing from it are dropped and the rest are ordered by rank.
+pub fn import(src: &str, length: usize, frequency: Option<&[String]>) -> Vec<Answer> {
+    let mut seen = HashSet::new();
+    let mut answers = src
+        .lines()
+        .filter_map(parse_line)
+        .filter(|entry| entry.is_consistent(length))
+        .filter(|entry| seen.insert(entry.simplified.clone()))
+        .map(Answer::from)
+        .collect::<Vec<_>>();
+
+    if let Some(frequency) = frequency {
+        let mut rank = HashMap::new();
+        for (i, word) in frequency.iter().enumerate() {
+            rank.entry(word.as_str()).or_insert(i);
+        }
+
+        answers.retain(|answer| rank.contains_key(answer.word.as_str()));
+        answers.sort_by_key(|answer| rank[answer.word.as_str()]);
+    }
+
+    answers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRC: &str = "\
+# CC-CEDICT
+堅定不移 坚定不移 [jian1 ding4 bu4 yi2] /firm and unswerving/unswerving/
+綠林好漢 绿林好汉 [lu:4 lin2 hao3 han4] /hero of the greenwood/
+東西 东西 [dong1 xi5] /thing/
+堅定不移 坚定不移 [jian1 ding4 bu2 yi2] /duplicate reading/
+";
+
+    #[test]
+    fn line_parsed() {
+        let entry = parse_line("中國 中国 [Zhong1 guo2] /China/").unwrap();
+
+        assert_eq!(entry.traditional, "中國");
+        assert_eq!(entry.simplified, "中国");
+        assert_eq!(entry.pinyin, "zhong1 guo2");
+        assert_eq!(entry.definitions, ["China"]);
+    }
+
+    #[test]
+    fn syllables_standardized() {
+        let entry = parse_line("綠 绿 [lu:4] /green/").unwrap();
+        assert_eq!(entry.pinyin, "lü4");
+        let entry = parse_line("嗎 吗 [ma5] /question particle/").unwrap();
+        assert_eq!(entry.pinyin, "ma");
+    }
+
+    #[test]
+    fn comments_and_malformed_lines_skipped() {
+        assert_eq!(parse_line("# comment"), None);
+        assert_eq!(parse_line("   "), None);
+        assert_eq!(parse_line("中國 中国 Zhong1 guo2 /China/"), None);
+    }
+
+    #[test]
+    fn import_keeps_first_reading_of_right_length() {
+        let answers = import(SRC, 4, None);
+        let words = answers.iter().map(|a| a.word.as_str()).collect::<Vec<_>>();
+
+        assert_eq!(words, ["坚定不移", "绿林好汉"]);
+        assert_eq!(answers[0].pinyin, "jian1 ding4 bu4 yi2");
+        assert_eq!(answers[0].explanation, "firm and unswerving; unswerving");
+    }
+
+    #[test]
+    fn import_ordered_by_frequency() {
+        let frequency = ["绿林好汉".to_string(), "一心一意".to_string()];
+        let answers = import(SRC, 4, Some(&frequency));
+        let words = answers.iter().map(|a| a.word.as_str()).collect::<Vec<_>>();
+
+        assert_eq!(words, ["绿林好汉"]);
+    }
+}
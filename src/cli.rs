@@ -0,0 +1,313 @@
+use handle::cedict;
+use handle::config::DictMode;
+use handle::constant::IDIOM_LENGTH;
+use handle::dict::{self, Dictionary, ValidationReport};
+use handle::error::{IdiomError, OmniError};
+use handle::model::{Answer, Attempt, Character, Scheme};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "\
+usage: handle dict <command>
+
+commands:
+    lint [--length N] [--scheme pinyin|jyutping|kana] [FILE...]
+                                      parse every entry of the given files (default: all word lists)
+    stats                             print counts and tone / initial distributions of every list
+    lookup <WORD> [--list NAME]       print the dictionary entry for a word
+    build-answers <FREQUENCY-FILE> --output FILE [--limit N]
+                                      derive the answer list from a frequency-ordered word list
+    import-cedict <CEDICT-FILE> --output FILE [--length N] [--frequency FILE] [--limit N]
+                                      convert a CC-CEDICT file into the `data/` JSON format";
+
+const DEFAULT_ANSWER_LIMIT: usize = 4000;
+
+fn usage(message: impl ToString) -> OmniError {
+    OmniError::Usage(format!("{}\n\n{USAGE}", message.to_string()))
+}
+
+/// Entry point for `handle dict ...`. Returns whether the command succeeded.
+pub fn dict(args: &[String]) -> Result<bool, OmniError> {
+    let Some((command, rest)) = args.split_first() else {
+        return Err(usage("missing command"));
+    };
+
+    match command.as_str() {
+        "lint" => lint(rest),
+        "stats" => stats().map(|_| true),
+        "lookup" => match rest {
+            [word] => lookup(word, None),
+            [word, flag, list] if flag == "--list" => lookup(word, Some(list)),
+            _ => Err(usage("expected exactly one word")),
+        },
+        "build-answers" => build_answers(rest),
+        "import-cedict" => import_cedict(rest).map(|_| true),
+        other => Err(usage(format!("unknown command `{other}`"))),
+    }
+}
+
+pub(crate) fn load() -> (Dictionary, ValidationReport) {
+    let mut report = ValidationReport::default();
+    let dictionary = Dictionary::load(DictMode::Degraded, &mut report).unwrap_or_default();
+    (dictionary, report)
+}
+
+/// Splits each entry into as many characters as its word has; list entries
+/// have already been checked against the list's length by the loader.
+fn characters(answer: &Answer, scheme: Scheme) -> Result<Vec<Character>, IdiomError> {
+    Attempt::from(answer).to_characters(answer.word.chars().count(), scheme)
+}
+
+fn lint(args: &[String]) -> Result<bool, OmniError> {
+    let mut length = None;
+    let mut scheme = Scheme::Pinyin;
+    let mut files = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--length" => {
+                length = Some(
+                    args.next()
+                        .and_then(|v| v.parse().ok())
+                        .ok_or_else(|| usage("`--length` expects a number"))?,
+                );
+            }
+            "--scheme" => {
+                scheme = match args.next().map(String::as_str) {
+                    Some("pinyin") => Scheme::Pinyin,
+                    Some("jyutping") => Scheme::Jyutping,
+                    Some("kana") => Scheme::Kana,
+                    _ => return Err(usage("`--scheme` expects `pinyin`, `jyutping` or `kana`")),
+                };
+            }
+            file => files.push(file),
+        }
+    }
+
+    let report = if files.is_empty() {
+        load().1
+    } else {
+        let mut report = ValidationReport::default();
+        for file in files {
+            dict::read_answers_from(Path::new(file), length, scheme, &mut report)?;
+        }
+        report
+    };
+
+    print!("{report}");
+    Ok(report.is_ok())
+}
+
+fn print_distribution(title: &str, distribution: &BTreeMap<String, usize>) {
+    let total = distribution.values().sum::<usize>().max(1);
+
+    println!("  {title}:");
+    for (key, count) in distribution {
+        println!(
+            "    {key:<6} {count:>7} {:>6.2}%",
+            *count as f64 * 100. / total as f64
+        );
+    }
+}
+
+fn print_stats(name: &str, answers: &[Answer], scheme: Scheme) {
+    let mut literals = HashSet::new();
+    let mut pronunciations = HashSet::new();
+    let mut tones = BTreeMap::new();
+    let mut initials = BTreeMap::new();
+
+    for answer in answers {
+        let Ok(characters) = characters(answer, scheme) else {
+            continue;
+        };
+
+        for character in characters {
+            literals.insert(character.literal);
+            pronunciations.insert(character.pinyin.pronunciation);
+
+            let tone = match character.pinyin.tone.0 {
+                Some(tone) => tone.number().to_string(),
+                None => "-".to_string(),
+            };
+            *tones.entry(tone).or_insert(0) += 1;
+
+            let initial = character.pinyin.initial.0.unwrap_or_else(|| "-".into());
+            *initials.entry(initial).or_insert(0) += 1;
+        }
+    }
+
+    println!("{name}:");
+    println!("  entries:                 {}", answers.len());
+    println!("  distinct characters:     {}", literals.len());
+    println!("  distinct pronunciations: {}", pronunciations.len());
+    print_distribution("tones", &tones);
+    print_distribution("initials", &initials);
+}
+
+fn stats() -> Result<(), OmniError> {
+    let (dictionary, _) = load();
+
+    for (name, list) in &dictionary.lists {
+        print_stats(&format!("{name} (dictionary)"), &list.all, list.scheme);
+        print_stats(&format!("{name} (answers)"), &list.answers, list.scheme);
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct LookupOutput<'a> {
+    #[serde(flatten)]
+    answer: &'a Answer,
+    characters: Vec<Character>,
+}
+
+fn lookup(word: &str, list: Option<&str>) -> Result<bool, OmniError> {
+    let (dictionary, _) = load();
+
+    let list = dictionary.list(list)?;
+    let Some(answer) = list.lookup(word) else {
+        eprintln!("`{word}` not found");
+        return Ok(false);
+    };
+
+    let output = LookupOutput {
+        answer,
+        characters: characters(answer, list.scheme)?,
+    };
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    Ok(true)
+}
+
+/// Same as `script/process-high-frequency.py`: keeps the first `limit` words of
+/// the frequency file (first column of each line) that exist in the dictionary.
+/// Loads the dictionary strictly, so a partial dictionary cannot silently
+/// shrink the answer list.
+fn build_answers(args: &[String]) -> Result<bool, OmniError> {
+    let mut frequency_file = None;
+    let mut limit = DEFAULT_ANSWER_LIMIT;
+    let mut output = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--limit" => {
+                limit = args
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(|| usage("`--limit` expects a number"))?;
+            }
+            "--output" => {
+                output = Some(
+                    args.next()
+                        .map(PathBuf::from)
+                        .ok_or_else(|| usage("`--output` expects a file"))?,
+                );
+            }
+            file if frequency_file.is_none() => frequency_file = Some(file),
+            other => return Err(usage(format!("unexpected argument `{other}`"))),
+        }
+    }
+    let frequency_file = frequency_file.ok_or_else(|| usage("missing frequency file"))?;
+    let output = output.ok_or_else(|| usage("missing `--output`"))?;
+
+    let dictionary = Dictionary::load(DictMode::Strict, &mut ValidationReport::default())?;
+    let list = dictionary.list(None)?;
+    let answers = read_frequency(frequency_file)?
+        .iter()
+        .filter_map(|word| list.lookup(word))
+        .take(limit)
+        .collect::<Vec<_>>();
+
+    if answers.is_empty() {
+        eprintln!("no word of `{frequency_file}` is in the dictionary, nothing written");
+        return Ok(false);
+    }
+    write_answers(&output, &answers)?;
+
+    Ok(true)
+}
+
+fn import_cedict(args: &[String]) -> Result<(), OmniError> {
+    let mut cedict_file = None;
+    let mut length = IDIOM_LENGTH;
+    let mut frequency_file = None;
+    let mut limit = None;
+    let mut output = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--length" => {
+                length = args
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(|| usage("`--length` expects a number"))?;
+            }
+            "--frequency" => {
+                frequency_file = Some(
+                    args.next()
+                        .ok_or_else(|| usage("`--frequency` expects a file"))?,
+                );
+            }
+            "--limit" => {
+                limit = Some(
+                    args.next()
+                        .and_then(|v| v.parse().ok())
+                        .ok_or_else(|| usage("`--limit` expects a number"))?,
+                );
+            }
+            "--output" => {
+                output = Some(
+                    args.next()
+                        .map(PathBuf::from)
+                        .ok_or_else(|| usage("`--output` expects a file"))?,
+                );
+            }
+            file if cedict_file.is_none() => cedict_file = Some(file),
+            other => return Err(usage(format!("unexpected argument `{other}`"))),
+        }
+    }
+    let cedict_file = cedict_file.ok_or_else(|| usage("missing CC-CEDICT file"))?;
+    let output = output.ok_or_else(|| usage("missing `--output`"))?;
+
+    let frequency = frequency_file
+        .map(|file| read_frequency(file))
+        .transpose()?;
+    let mut answers = cedict::import(
+        &fs::read_to_string(cedict_file)?,
+        length,
+        frequency.as_deref(),
+    );
+    if let Some(limit) = limit {
+        answers.truncate(limit);
+    }
+
+    write_answers(&output, &answers)
+}
+
+/// Reads the first column of each line of a frequency-ordered word list.
+fn read_frequency(file: &str) -> Result<Vec<String>, OmniError> {
+    Ok(fs::read_to_string(file)?
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .map(ToString::to_string)
+        .collect())
+}
+
+/// Writes answers in the same layout as the files under `data/`.
+fn write_answers(output: &Path, answers: &[impl Serialize]) -> Result<(), OmniError> {
+    let mut buf = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut buf, formatter);
+    answers.serialize(&mut serializer)?;
+    fs::write(output, buf)?;
+
+    println!("wrote {} answers to {}", answers.len(), output.display());
+
+    Ok(())
+}
@@ -0,0 +1,480 @@
+//! Allocation-free scoring for solvers and simulations. Characters are
+//! interned once into `CompactWord`s; `score` then works on symbols and
+//! bitmasks only and gives the same `CharacterResult`s as
+//! `CalculatedAttempt::from_attempt`.
+//!
+//! The `from_attempt` counts are keyed by value, so which answer position a
+//! match uses up does not matter. Here a match takes the first unused answer
+//! position holding the value, which leaves the same counts behind.
+
+use crate::evaluator::Mode;
+use crate::model::{
+    CharInfo, Character, CharacterResult, MandarinTone, ScoringOptions, State, Tone, ToneExplicit,
+};
+use crate::sandhi::{self, Sandhi};
+use std::collections::HashMap;
+
+/// Longest word `CompactWord` can hold.
+pub const MAX_LENGTH: usize = 16;
+
+/// An interned string.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Symbol(u32);
+
+/// Hands out one `Symbol` per distinct string. Words compared with each other
+/// must come from the same interner.
+#[derive(Debug, Default)]
+pub struct Interner {
+    symbols: HashMap<String, Symbol>,
+}
+
+impl Interner {
+    /// The symbol of `value`, new if it has not been seen.
+    pub fn intern(&mut self, value: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(value) {
+            return *symbol;
+        }
+
+        let symbol = Symbol(self.symbols.len() as u32);
+        self.symbols.insert(value.to_string(), symbol);
+        symbol
+    }
+
+    /// Interns a word, with its tones already normalized by `sandhi`. `None` if
+    /// it is longer than `MAX_LENGTH`.
+    pub fn encode(&mut self, characters: &[Character], sandhi: Sandhi) -> Option<CompactWord> {
+        if characters.len() > MAX_LENGTH {
+            return None;
+        }
+
+        let mut word = CompactWord {
+            len: characters.len() as u8,
+            ..Default::default()
+        };
+        let tones = sandhi::spoken_tones(characters, sandhi);
+        for ((compact, character), tone) in word.chars.iter_mut().zip(characters).zip(tones) {
+            *compact = CompactChar {
+                literal: self.intern(&character.literal()),
+                pronunciation: self.intern(&character.pronunciation()),
+                initial: character.initial().0.map(|initial| self.intern(&initial)),
+                vowel: character.vowel().map(|vowel| self.intern(&vowel)),
+                tone,
+            };
+        }
+
+        Some(word)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct CompactChar {
+    literal: Symbol,
+    pronunciation: Symbol,
+    initial: Option<Symbol>,
+    vowel: Option<Symbol>,
+    tone: Tone,
+}
+
+impl Default for CompactChar {
+    fn default() -> Self {
+        Self {
+            literal: Symbol::default(),
+            pronunciation: Symbol::default(),
+            initial: None,
+            vowel: None,
+            tone: Tone(None),
+        }
+    }
+}
+
+/// A word of interned characters, made by `Interner::encode`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CompactWord {
+    len: u8,
+    chars: [CompactChar; MAX_LENGTH],
+}
+
+impl CompactWord {
+    /// Number of characters, at most `MAX_LENGTH`.
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// Whether the word has no characters.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn chars(&self) -> &[CompactChar] {
+        &self.chars[..self.len()]
+    }
+}
+
+/// `ScoringOptions` with the fuzzy table interned. Without fuzzy matching
+/// both tables are empty.
+#[derive(Debug, Default)]
+pub struct CompactRules {
+    /// Feedback given, as `ScoringOptions::mode`.
+    pub mode: Mode,
+    /// Sandhi the words were encoded with, see `Interner::encode`.
+    pub sandhi: Sandhi,
+    legacy_neutral_tone: bool,
+    fuzzy_initials: Vec<(Symbol, Symbol)>,
+    fuzzy_vowels: Vec<(Symbol, Symbol)>,
+}
+
+impl CompactRules {
+    /// Interns the fuzzy pairs of `options` with the interner of the words.
+    pub fn new(options: &ScoringOptions, interner: &mut Interner) -> Self {
+        let mut intern = |pairs: &[(String, String)]| {
+            pairs
+                .iter()
+                .map(|(a, b)| (interner.intern(a), interner.intern(b)))
+                .collect::<Vec<_>>()
+        };
+
+        Self {
+            mode: options.mode,
+            sandhi: options.effective_sandhi(),
+            legacy_neutral_tone: options.legacy_neutral_tone,
+            fuzzy_initials: options
+                .fuzzy
+                .map(|fuzzy| intern(&fuzzy.initials))
+                .unwrap_or_default(),
+            fuzzy_vowels: options
+                .fuzzy
+                .map(|fuzzy| intern(&fuzzy.vowels))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+fn is_near(pairs: &[(Symbol, Symbol)], a: Symbol, b: Symbol) -> bool {
+    pairs
+        .iter()
+        .any(|&(x, y)| (x == a && y == b) || (x == b && y == a))
+}
+
+/// `CharacterResult`s of a scored `CompactWord`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CompactResult {
+    len: u8,
+    results: [CharacterResult; MAX_LENGTH],
+}
+
+impl CompactResult {
+    /// One result per character of the guess.
+    pub fn results(&self) -> &[CharacterResult] {
+        &self.results[..self.len as usize]
+    }
+}
+
+/// Answer positions whose counts are used up, one bit per position.
+#[derive(Default)]
+struct Used {
+    whole: u32,
+    pronunciation: u32,
+    initial: u32,
+    vowel: u32,
+    tone: u32,
+}
+
+/// The key a tone is counted under, its digit. Toneless syllables are never
+/// counted, neither is the neutral tone with `legacy_neutral_tone`.
+fn tone_key(tone: Tone, legacy_neutral_tone: bool) -> Option<u8> {
+    match tone.mandarin() {
+        Some(MandarinTone::Neutral) if legacy_neutral_tone => None,
+        _ => tone.0.map(ToneExplicit::number),
+    }
+}
+
+/// Uses up the first unused answer position whose key is `value`.
+fn take<K: PartialEq>(
+    used: &mut u32,
+    answer: &[CompactChar],
+    value: Option<K>,
+    key: impl Fn(&CompactChar) -> Option<K>,
+) -> bool {
+    let Some(value) = value else {
+        return false;
+    };
+
+    for (j, ans_char) in answer.iter().enumerate() {
+        if *used & (1 << j) == 0 && key(ans_char).as_ref() == Some(&value) {
+            *used |= 1 << j;
+            return true;
+        }
+    }
+
+    false
+}
+
+/// One exact pass and one misplaced pass over a single dimension.
+fn pass<K: PartialEq>(
+    answer: &[CompactChar],
+    guess: &[CompactChar],
+    results: &mut [CharacterResult],
+    used: &mut u32,
+    key: impl Fn(&CompactChar) -> Option<K> + Copy,
+    exact: impl Fn(&CompactChar, &CompactChar) -> bool,
+    state: impl Fn(&mut CharacterResult) -> &mut State,
+) {
+    for ((char, ans_char), result) in guess.iter().zip(answer).zip(results.iter_mut()) {
+        if *state(result) == State::Missing && exact(char, ans_char) {
+            take(used, answer, key(char), key);
+            *state(result) = State::Correct;
+        }
+    }
+    for (char, result) in guess.iter().zip(results.iter_mut()) {
+        if *state(result) == State::Missing && take(used, answer, key(char), key) {
+            *state(result) = State::Misplaced;
+        }
+    }
+}
+
+/// Scores `guess` against `answer`, both encoded by the same `Interner` and of
+/// the same length.
+pub fn score(answer: &CompactWord, guess: &CompactWord, rules: &CompactRules) -> CompactResult {
+    debug_assert_eq!(answer.len(), guess.len());
+
+    let mut res = CompactResult {
+        len: answer.len,
+        ..Default::default()
+    };
+    let (answer, guess) = (answer.chars(), guess.chars());
+    let results = &mut res.results[..answer.len()];
+    let mut used = Used::default();
+    let legacy = rules.legacy_neutral_tone;
+
+    let (whole, consume, pronunciation, initial, vowel, tone) = match rules.mode {
+        Mode::Standard => (true, false, true, true, true, true),
+        Mode::Pronunciation => (false, false, true, true, true, true),
+        Mode::Toneless => (true, false, true, true, true, false),
+        Mode::Classic => (true, false, false, false, false, false),
+        Mode::Consuming => (true, true, true, true, true, true),
+    };
+
+    if whole {
+        pass(
+            answer,
+            guess,
+            results,
+            &mut used.whole,
+            |c| Some(c.literal),
+            |a, b| a.literal == b.literal,
+            |r| &mut r.whole,
+        );
+    }
+
+    if consume {
+        for (char, result) in guess.iter().zip(results.iter_mut()) {
+            let state = result.whole;
+            if state == State::Missing {
+                continue;
+            }
+            if take(
+                &mut used.pronunciation,
+                answer,
+                Some(char.pronunciation),
+                |c| Some(c.pronunciation),
+            ) {
+                result.pronunciation = state;
+            }
+            if take(&mut used.initial, answer, char.initial, |c| c.initial) {
+                result.initial = state;
+            }
+            if take(&mut used.vowel, answer, char.vowel, |c| c.vowel) {
+                result.vowel = state;
+            }
+            if take(&mut used.tone, answer, tone_key(char.tone, legacy), |c| {
+                tone_key(c.tone, legacy)
+            }) {
+                result.tone = state;
+            }
+        }
+    }
+
+    if pronunciation {
+        pass(
+            answer,
+            guess,
+            results,
+            &mut used.pronunciation,
+            |c| Some(c.pronunciation),
+            |a, b| a.pronunciation == b.pronunciation,
+            |r| &mut r.pronunciation,
+        );
+    }
+
+    if initial {
+        pass(
+            answer,
+            guess,
+            results,
+            &mut used.initial,
+            |c| c.initial,
+            |a, b| a.initial == b.initial,
+            |r| &mut r.initial,
+        );
+        for ((char, ans_char), result) in guess.iter().zip(answer).zip(results.iter_mut()) {
+            if result.initial == State::Missing
+                && let (Some(a), Some(b)) = (char.initial, ans_char.initial)
+                && is_near(&rules.fuzzy_initials, a, b)
+                && take(&mut used.initial, answer, Some(b), |c| c.initial)
+            {
+                result.initial = State::Near;
+            }
+        }
+    }
+
+    if vowel {
+        pass(
+            answer,
+            guess,
+            results,
+            &mut used.vowel,
+            |c| c.vowel,
+            |a, b| a.vowel.is_some() && a.vowel == b.vowel,
+            |r| &mut r.vowel,
+        );
+        for ((char, ans_char), result) in guess.iter().zip(answer).zip(results.iter_mut()) {
+            if result.vowel == State::Missing
+                && let (Some(a), Some(b)) = (char.vowel, ans_char.vowel)
+                && is_near(&rules.fuzzy_vowels, a, b)
+                && take(&mut used.vowel, answer, Some(b), |c| c.vowel)
+            {
+                result.vowel = State::Near;
+            }
+        }
+    }
+
+    if tone {
+        pass(
+            answer,
+            guess,
+            results,
+            &mut used.tone,
+            |c| tone_key(c.tone, legacy),
+            |a, b| a.tone.0.is_some() && a.tone == b.tone,
+            |r| &mut r.tone,
+        );
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fuzzy::FuzzyTable;
+    use crate::model::{Attempt, CalculatedAttempt, Scheme};
+
+    /// Repeated characters, 一 and 不, third tones in a row, neutral tones and
+    /// fuzzy-equivalent initials and vowels.
+    const WORDS: &[(&str, &str)] = &[
+        ("一心一意", "yi1 xin1 yi1 yi4"),
+        ("一五一十", "yi1 wu3 yi1 shi2"),
+        ("不三不四", "bu4 san1 bu4 si4"),
+        ("赞不绝口", "zan4 bu4 jue2 kou3"),
+        ("掌上明珠", "zhang3 shang4 ming2 zhu1"),
+        ("走马观花", "zou3 ma3 guan1 hua1"),
+        ("心心相印", "xin1 xin1 xiang1 yin4"),
+        ("好好先生", "hao3 hao3 xian1 sheng0"),
+        ("东西南北", "dong1 xi0 nan2 bei3"),
+        ("来来往往", "lai2 lai2 wang3 wang3"),
+        ("长长久久", "chang2 zhang3 jiu3 jiu3"),
+        ("长长久久", "chang3 chang3 jiu3 jiu3"),
+    ];
+
+    #[test]
+    fn agrees_with_from_attempt() {
+        let words = WORDS
+            .iter()
+            .map(|(word, pinyin)| {
+                let attempt = Attempt {
+                    word: word.to_string(),
+                    pinyin: pinyin.to_string(),
+                    verified: None,
+                    guesser: None,
+                };
+                let characters = attempt.to_characters(4, Scheme::Pinyin).unwrap();
+                (attempt, characters)
+            })
+            .collect::<Vec<_>>();
+        let fuzzy = FuzzyTable::default();
+
+        let modes = [
+            Mode::Standard,
+            Mode::Pronunciation,
+            Mode::Toneless,
+            Mode::Classic,
+            Mode::Consuming,
+        ];
+        for mode in modes {
+            for sandhi in [Sandhi::Off, Sandhi::Yibu, Sandhi::Full] {
+                for (fuzzy, legacy_neutral_tone) in
+                    [(None, false), (Some(&fuzzy), false), (None, true)]
+                {
+                    let options = ScoringOptions {
+                        scheme: Scheme::Pinyin,
+                        fuzzy,
+                        legacy_neutral_tone,
+                        sandhi,
+                        mode,
+                    };
+                    let mut interner = Interner::default();
+                    let rules = CompactRules::new(&options, &mut interner);
+                    let encoded = words
+                        .iter()
+                        .map(|(_, characters)| interner.encode(characters, rules.sandhi).unwrap())
+                        .collect::<Vec<_>>();
+
+                    for (answer, (_, answer_characters)) in encoded.iter().zip(&words) {
+                        for (guess, (guess_attempt, _)) in encoded.iter().zip(&words) {
+                            let expected = CalculatedAttempt::from_attempt(
+                                answer_characters,
+                                guess_attempt,
+                                &options,
+                            )
+                            .unwrap()
+                            .characters
+                            .into_iter()
+                            .map(|character| character.result)
+                            .collect::<Vec<_>>();
+                            let actual = score(answer, guess, &rules);
+
+                            assert_eq!(
+                                actual.results(),
+                                expected,
+                                "{} against {} with {options:?}",
+                                guess_attempt.word,
+                                answer_characters
+                                    .iter()
+                                    .map(CharInfo::literal)
+                                    .collect::<String>(),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn too_long_to_encode() {
+        let attempt = Attempt {
+            word: "一".repeat(MAX_LENGTH + 1),
+            pinyin: vec!["yi1"; MAX_LENGTH + 1].join(" "),
+            verified: None,
+            guesser: None,
+        };
+        let characters = attempt
+            .to_characters(MAX_LENGTH + 1, Scheme::Pinyin)
+            .unwrap();
+        assert!(
+            Interner::default()
+                .encode(&characters, Sandhi::Off)
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,33 @@
+//! Runtime configuration, read from the environment.
+
+use std::env;
+
+/// What to do when the dictionary fails to load or validate at startup.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DictMode {
+    /// Refuse to start on any read, parse or validation error.
+    #[default]
+    Strict,
+
+    /// Skip broken files and entries, and serve whatever is left.
+    Degraded,
+}
+
+/// Server settings.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// How dictionary errors are handled at startup.
+    pub dict_mode: DictMode,
+}
+
+impl Config {
+    /// Reads `HANDLE_DICT_MODE` (`strict` or `degraded`), strict by default.
+    pub fn from_env() -> Self {
+        let dict_mode = match env::var("HANDLE_DICT_MODE").as_deref() {
+            Ok("degraded") => DictMode::Degraded,
+            _ => DictMode::Strict,
+        };
+
+        Self { dict_mode }
+    }
+}
@@ -1,10 +1,23 @@
+//! Game-wide constants.
+
+/// Length of an idiom, and of words in lists that do not set theirs.
 pub const IDIOM_LENGTH: usize = 4;
+/// Guesses a player gets before the game is lost.
 pub const MAX_ATTEMPT_COUNT: usize = 10;
 
 // pub const TONES: &str = "01234";
+/// Letters a pinyin initial can start with.
 pub const INITIALS: &str = "bpmfdtnlgkhjqxzcsryw";
+/// Initials that take an `h` to form `zh`, `ch` and `sh`.
 pub const SPECIAL_INITIALS: &str = "zcs";
+/// Jyutping initials, two-letter initials first, so that they win over their
+/// one-letter prefixes.
+pub const JYUTPING_INITIALS: [&str; 19] = [
+    "ng", "gw", "kw", "b", "p", "m", "f", "d", "t", "n", "l", "g", "k", "h", "w", "z", "c", "s",
+    "j",
+];
 
+/// Typst templates and the data files rendered with them.
 pub const IMAGE_DIR: &str = "image/";
 // pub const DATA_FILE: &str = "data.json";
 // pub const IMAGE_FILE: &str = "handle-1.png";
@@ -0,0 +1,167 @@
+//! Cooperative play: a group guesses together on one shared board, each
+//! attempt carrying its `model::Guesser`. The board itself is kept by the
+//! client like any other; only guess cooldowns are tracked here, in memory.
+
+use crate::error::CoopError;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Guess times older than this are forgotten, whatever the cooldown.
+const MAX_COOLDOWN: Duration = Duration::from_secs(24 * 60 * 60);
+
+lazy_static! {
+    static ref GAMES: Mutex<HashMap<String, Game>> = Mutex::new(HashMap::new());
+}
+
+/// What has been seen of a shared board so far.
+#[derive(Default)]
+struct Game {
+    /// Attempts on the board when it was last sent. Boards are resent whole,
+    /// so only a longer one carries a new guess.
+    attempts: usize,
+    /// Time of the last guess, by player id.
+    last_guess: HashMap<String, Instant>,
+}
+
+/// A guess recorded by `reserve`, taken back when dropped unless `commit`ted.
+#[must_use]
+pub struct Reservation {
+    game_id: String,
+    player_id: String,
+    attempts: usize,
+    time: Instant,
+    /// What the guess replaced, to restore on release.
+    previous: (usize, Option<Instant>),
+    committed: bool,
+}
+
+impl Reservation {
+    /// Keeps the guess.
+    pub fn commit(mut self) {
+        self.committed = true;
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        if self.committed {
+            return;
+        }
+
+        let mut games = GAMES.lock().unwrap();
+        let Some(game) = games.get_mut(&self.game_id) else {
+            return;
+        };
+        // a later guess made meanwhile stays
+        if game.attempts == self.attempts {
+            game.attempts = self.previous.0;
+        }
+        if game.last_guess.get(&self.player_id) == Some(&self.time) {
+            match self.previous.1 {
+                Some(time) => game.last_guess.insert(self.player_id.clone(), time),
+                None => game.last_guess.remove(&self.player_id),
+            };
+        }
+    }
+}
+
+/// Records the guess of `player_id` if a board of `attempts` in `game_id`
+/// carries a new one, failing if the previous guess of `player_id` was less
+/// than `cooldown` ago. None if the board carries no new guess. Checking and
+/// recording happen at once, so two boards sent together cannot both pass.
+pub fn reserve(
+    game_id: &str,
+    player_id: &str,
+    attempts: usize,
+    cooldown: Duration,
+) -> Result<Option<Reservation>, CoopError> {
+    let mut games = GAMES.lock().unwrap();
+    games.retain(|_, game| {
+        game.last_guess
+            .retain(|_, time| time.elapsed() < MAX_COOLDOWN);
+        !game.last_guess.is_empty()
+    });
+
+    let game = games.entry(game_id.to_string()).or_default();
+    if attempts <= game.attempts {
+        return Ok(None);
+    }
+
+    let last_guess = game.last_guess.get(player_id).copied();
+    if let Some(time) = last_guess {
+        let elapsed = time.elapsed();
+        if elapsed < cooldown {
+            let wait = (cooldown - elapsed).as_secs_f64().ceil() as u64;
+            return Err(CoopError::Cooldown(player_id.to_string(), wait));
+        }
+    }
+
+    let time = Instant::now();
+    let previous = (game.attempts, last_guess);
+    game.attempts = attempts;
+    game.last_guess.insert(player_id.to_string(), time);
+
+    Ok(Some(Reservation {
+        game_id: game_id.to_string(),
+        player_id: player_id.to_string(),
+        attempts,
+        time,
+        previous,
+        committed: false,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    const COOLDOWN: Duration = Duration::from_secs(60);
+
+    fn game_id(name: &str) -> String {
+        format!("test-{}-{name}", process::id())
+    }
+
+    #[test]
+    fn cooldown_after_committed_guess() {
+        let game_id = game_id("cooldown");
+        reserve(&game_id, "ann", 1, COOLDOWN)
+            .unwrap()
+            .unwrap()
+            .commit();
+
+        assert!(reserve(&game_id, "ann", 2, COOLDOWN).is_err());
+        assert!(reserve(&game_id, "bob", 2, COOLDOWN).unwrap().is_some());
+    }
+
+    #[test]
+    fn resent_board_not_a_guess() {
+        let game_id = game_id("resent");
+        reserve(&game_id, "ann", 2, COOLDOWN)
+            .unwrap()
+            .unwrap()
+            .commit();
+
+        assert!(reserve(&game_id, "bob", 2, COOLDOWN).unwrap().is_none());
+        assert!(reserve(&game_id, "bob", 1, COOLDOWN).unwrap().is_none());
+    }
+
+    #[test]
+    fn concurrent_guess_waits_for_cooldown() {
+        let game_id = game_id("concurrent");
+        let first = reserve(&game_id, "ann", 1, COOLDOWN).unwrap();
+        assert!(first.is_some());
+        assert!(reserve(&game_id, "ann", 2, COOLDOWN).is_err());
+    }
+
+    #[test]
+    fn dropped_guess_released() {
+        let game_id = game_id("released");
+        drop(reserve(&game_id, "ann", 1, COOLDOWN).unwrap());
+
+        let again = reserve(&game_id, "ann", 1, COOLDOWN).unwrap();
+        assert!(again.is_some());
+    }
+}
@@ -1,42 +1,589 @@
-use crate::model::Answer;
-use lazy_static::lazy_static;
-use std::{collections::HashMap, fs, path::PathBuf};
+//! The dictionary: word lists under `data/`, loaded and validated once at
+//! startup, with the fuzzy-pinyin and variant tables that go with them.
 
-fn data_dir() -> PathBuf {
-    "data".into()
-}
+use crate::config::DictMode;
+use crate::constant::IDIOM_LENGTH;
+use crate::error::{DictError, IdiomError};
+use crate::fuzzy::{FUZZY_FILE, FuzzyTable};
+use crate::model::{Answer, Attempt, Scheme};
+use crate::variant::{VARIANTS_FILE, VariantTable};
+use serde::Deserialize;
+use std::fmt::{self, Display, Formatter};
+use std::sync::OnceLock;
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+/// Name of the idiom list read from the top of `data/`.
+pub const DEFAULT_LIST: &str = "idiom";
+
+/// Every word of the default list, the ones guesses are looked up in.
+pub const ALL_FILE: &str = "all.json";
+/// Words of the default list answers are drawn from.
+pub const ANSWERS_FILE: &str = "high-frequency.json";
 
-fn read_answers_from(file_name: &str) -> Vec<Answer> {
-    let path = data_dir().join(file_name);
-    let data = fs::read_to_string(&path).unwrap_or_else(|err| {
-        panic!("failed to read {}: {}", path.display(), err);
-    });
+const LISTS_DIR: &str = "lists";
+const LIST_ANSWERS_FILE: &str = "answers.json";
+const META_FILE: &str = "list.json";
 
-    serde_json::from_str(&data).unwrap_or_else(|err| {
-        panic!("failed to parse {}: {}", path.display(), err);
+/// Directory the dictionary and every stored file live under: `data`, or
+/// `HANDLE_DATA_DIR` if set. Unit tests get a directory of their own under
+/// the system's temporary directory, so that they never touch `data`.
+pub fn data_dir() -> PathBuf {
+    static DIR: OnceLock<PathBuf> = OnceLock::new();
+
+    DIR.get_or_init(|| {
+        if cfg!(test) {
+            return env::temp_dir().join(format!("handle-test-{}", process::id()));
+        }
+        env::var_os("HANDLE_DATA_DIR").map_or_else(|| "data".into(), PathBuf::from)
     })
+    .clone()
+}
+
+/// Replaces the file at `path` by writing `contents` next to it and renaming
+/// it over, so that a crash never leaves a half-written file behind.
+pub(crate) fn write_file(path: &Path, contents: &str) -> io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)
+}
+
+/// What is wrong with an entry or a file.
+#[derive(Debug)]
+pub enum IssueKind {
+    /// The word is listed twice.
+    Duplicate {
+        /// Line of its first entry, if known.
+        first_line: Option<usize>,
+    },
+    /// The entry does not parse.
+    Invalid(IdiomError),
+    /// The file cannot be read or parsed.
+    Unreadable(DictError),
+    /// The list directory has the name of the default list, which it cannot
+    /// replace.
+    Reserved,
+    /// The answer is not in the list's `all.json`, so it could never be
+    /// guessed.
+    UnknownAnswer,
+}
+
+/// A problem found while loading the dictionary.
+#[derive(Debug)]
+pub struct Issue {
+    /// Path of the file.
+    pub file: String,
+    /// 1-based line of the entry, if the problem is with one.
+    pub line: Option<usize>,
+    /// Word of the entry, if the problem is with one.
+    pub word: Option<String>,
+    /// The problem.
+    pub kind: IssueKind,
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.file)?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        if let Some(word) = &self.word {
+            write!(f, ": `{word}`")?;
+        }
+
+        match &self.kind {
+            IssueKind::Duplicate {
+                first_line: Some(first_line),
+            } => write!(f, ": duplicate of line {first_line}"),
+            IssueKind::Duplicate { first_line: None } => write!(f, ": duplicate word"),
+            IssueKind::Invalid(err) => write!(f, ": {err}"),
+            IssueKind::Unreadable(err) => write!(f, ": {err}"),
+            IssueKind::Reserved => write!(f, ": `{DEFAULT_LIST}` is the default list"),
+            IssueKind::UnknownAnswer => write!(f, ": not in `{ALL_FILE}`"),
+        }
+    }
 }
 
-fn read_all() -> Vec<Answer> {
-    read_answers_from("all.json")
+/// Outcome of loading the dictionary.
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    /// Entries read, valid or not.
+    pub checked: usize,
+    /// Every problem found, in the order found.
+    pub issues: Vec<Issue>,
 }
 
-fn read_answers() -> Vec<Answer> {
-    read_answers_from("high-frequency.json")
+impl ValidationReport {
+    /// Whether nothing was wrong.
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
 }
 
-fn reserve_index() -> HashMap<String, usize> {
-    let mut res = HashMap::new();
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "checked {} entries, found {} issue(s)",
+            self.checked,
+            self.issues.len()
+        )?;
+        for issue in &self.issues {
+            writeln!(f, "  {issue}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the 1-based line on which each element of a top-level JSON array starts.
+fn element_lines(src: &str) -> Vec<usize> {
+    let mut res = Vec::new();
+    let (mut line, mut depth) = (1, 0usize);
+    let (mut in_string, mut escaped, mut expecting) = (false, false, false);
+
+    for c in src.chars() {
+        if c == '\n' {
+            line += 1;
+        }
+
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        if c.is_whitespace() {
+            continue;
+        }
+
+        if expecting && depth == 1 && c != ']' {
+            res.push(line);
+        }
+        expecting = false;
 
-    for (i, v) in DICT.iter().enumerate() {
-        res.entry(v.word.clone()).or_insert(i);
+        match c {
+            '"' => in_string = true,
+            '[' | '{' => {
+                depth += 1;
+                expecting = depth == 1;
+            }
+            ']' | '}' => depth = depth.saturating_sub(1),
+            ',' => expecting = depth == 1,
+            _ => {}
+        }
     }
 
     res
 }
 
-lazy_static! {
-    pub static ref DICT: Vec<Answer> = read_all();
-    pub static ref ANSWERS: Vec<Answer> = read_answers();
-    pub static ref REVERSE_ANSWERS: HashMap<String, usize> = reserve_index();
+/// Reads a list of answers, dropping (and reporting) entries that do not parse
+/// into `length` characters (or as many as the word has, if `None`) or repeat
+/// an earlier word.
+pub fn read_answers_from(
+    path: &Path,
+    length: Option<usize>,
+    scheme: Scheme,
+    report: &mut ValidationReport,
+) -> Result<Vec<Answer>, DictError> {
+    let file = path.display().to_string();
+
+    let data = fs::read_to_string(path).map_err(|err| DictError::Read(file.clone(), err))?;
+    let entries: Vec<Answer> =
+        serde_json::from_str(&data).map_err(|err| DictError::Parse(file.clone(), err))?;
+    let lines = element_lines(&data);
+
+    let mut seen: HashMap<&str, Option<usize>> = HashMap::new();
+    let mut valid = vec![false; entries.len()];
+
+    for (i, entry) in entries.iter().enumerate() {
+        report.checked += 1;
+        let line = lines.get(i).copied();
+        let mut issue = |kind| {
+            report.issues.push(Issue {
+                file: file.clone(),
+                line,
+                word: Some(entry.word.clone()),
+                kind,
+            })
+        };
+
+        if let Some(&first_line) = seen.get(entry.word.as_str()) {
+            issue(IssueKind::Duplicate { first_line });
+            continue;
+        }
+        seen.insert(&entry.word, line);
+
+        let length = length.unwrap_or_else(|| entry.word.chars().count());
+        match Attempt::from(entry).to_characters(length, scheme) {
+            Ok(_) => valid[i] = true,
+            Err(err) => issue(IssueKind::Invalid(err)),
+        }
+    }
+
+    Ok(entries
+        .into_iter()
+        .zip(valid)
+        .filter_map(|(entry, valid)| valid.then_some(entry))
+        .collect())
+}
+
+/// Optional `list.json` next to a list's data files.
+#[derive(Debug, Default, Deserialize)]
+struct ListMeta {
+    length: Option<usize>,
+    #[serde(default)]
+    scheme: Scheme,
+}
+
+/// A named word list: the pool answers are drawn from, and the dictionary
+/// guesses are looked up in.
+#[derive(Debug, Default)]
+pub struct WordList {
+    /// Required word length, or `None` if words of any length are allowed.
+    pub length: Option<usize>,
+    /// Scheme the `pinyin` field of every entry is written in.
+    pub scheme: Scheme,
+    /// Every valid word, in file order.
+    pub all: Vec<Answer>,
+    /// Words answers are drawn from.
+    pub answers: Vec<Answer>,
+    reverse_index: HashMap<String, usize>,
+}
+
+impl WordList {
+    pub(crate) fn new(
+        length: Option<usize>,
+        scheme: Scheme,
+        all: Vec<Answer>,
+        answers: Vec<Answer>,
+    ) -> Self {
+        let mut reverse_index = HashMap::new();
+        for (i, v) in all.iter().enumerate() {
+            reverse_index.entry(v.word.clone()).or_insert(i);
+        }
+
+        Self {
+            length,
+            scheme,
+            all,
+            answers,
+            reverse_index,
+        }
+    }
+
+    /// Leaves out, reporting them, the answers that are not in `all`. Skipped
+    /// when `all` is empty, as it was then reported as unreadable already.
+    fn retain_known_answers(&mut self, answers_path: &Path, report: &mut ValidationReport) {
+        if self.all.is_empty() {
+            return;
+        }
+
+        let file = answers_path.display().to_string();
+        self.answers.retain(|answer| {
+            let known = self.reverse_index.contains_key(&answer.word);
+            if !known {
+                report.issues.push(Issue {
+                    file: file.clone(),
+                    line: None,
+                    word: Some(answer.word.clone()),
+                    kind: IssueKind::UnknownAnswer,
+                });
+            }
+            known
+        });
+    }
+
+    /// The entry of `word`, as written.
+    pub fn lookup(&self, word: &str) -> Option<&Answer> {
+        self.reverse_index.get(word).map(|index| &self.all[*index])
+    }
+
+    /// The form `word` is scored and looked up in: the word itself if it is
+    /// listed, or else its simplified form. Folding only on a miss keeps
+    /// characters that are also simplified, such as the 乾 of 乾坤, intact.
+    pub fn normalize(&self, word: &str, variants: &VariantTable) -> String {
+        if self.reverse_index.contains_key(word) {
+            word.to_string()
+        } else {
+            variants.simplify(word)
+        }
+    }
+}
+
+/// Every word list, with the tables shared by all of them.
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    /// Lists by name, `DEFAULT_LIST` being the idiom list.
+    pub lists: BTreeMap<String, WordList>,
+    /// `data/STCharacters.txt`, empty if it is missing, which only `Degraded`
+    /// mode allows.
+    pub variants: VariantTable,
+    /// `data/fuzzy.json`, or the built-in table.
+    pub fuzzy: FuzzyTable,
+}
+
+impl Dictionary {
+    /// Loads and validates the default idiom list from `data/` and every list
+    /// under `data/lists/<name>/` (`all.json`, optional `answers.json` and
+    /// `list.json` with `length` and `scheme`).
+    ///
+    /// In `Strict` mode any unreadable file, invalid entry or answer missing
+    /// from its list's `all.json` is an error; in `Degraded` mode they are
+    /// left out and only recorded in the report. A `data/lists/idiom/`
+    /// directory is never loaded, as the default list comes from `data/`.
+    pub fn load(mode: DictMode, report: &mut ValidationReport) -> Result<Self, DictError> {
+        let unreadable = |path: &Path, err, report: &mut ValidationReport| {
+            if mode == DictMode::Strict {
+                return Err(err);
+            }
+            report.issues.push(Issue {
+                file: path.display().to_string(),
+                line: None,
+                word: None,
+                kind: IssueKind::Unreadable(err),
+            });
+            Ok(())
+        };
+        let read = |path: &Path, length, scheme, report: &mut ValidationReport| {
+            read_answers_from(path, length, scheme, report)
+                .or_else(|err| unreadable(path, err, report).map(|_| Vec::new()))
+        };
+
+        let mut lists = BTreeMap::new();
+
+        let length = Some(IDIOM_LENGTH);
+        let scheme = Scheme::Pinyin;
+        let all = read(&data_dir().join(ALL_FILE), length, scheme, report)?;
+        let answers_path = data_dir().join(ANSWERS_FILE);
+        let answers = read(&answers_path, length, scheme, report)?;
+        let mut list = WordList::new(length, scheme, all, answers);
+        list.retain_known_answers(&answers_path, report);
+        lists.insert(DEFAULT_LIST.to_string(), list);
+
+        let mut dirs = match fs::read_dir(data_dir().join(LISTS_DIR)) {
+            Ok(entries) => entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect::<Vec<_>>(),
+            Err(_) => Vec::new(),
+        };
+        dirs.sort();
+
+        for dir in dirs {
+            let Some(name) = dir
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+            else {
+                continue;
+            };
+            if name == DEFAULT_LIST {
+                report.issues.push(Issue {
+                    file: dir.display().to_string(),
+                    line: None,
+                    word: None,
+                    kind: IssueKind::Reserved,
+                });
+                continue;
+            }
+
+            let meta_path = dir.join(META_FILE);
+            let meta = match fs::read_to_string(&meta_path) {
+                Ok(data) => serde_json::from_str(&data)
+                    .map_err(|err| DictError::Parse(meta_path.display().to_string(), err)),
+                Err(_) => Ok(ListMeta::default()),
+            };
+            let meta = match meta {
+                Ok(meta) => meta,
+                Err(err) => {
+                    unreadable(&meta_path, err, report)?;
+                    continue;
+                }
+            };
+
+            let all = read(&dir.join(ALL_FILE), meta.length, meta.scheme, report)?;
+            let answers_path = dir.join(LIST_ANSWERS_FILE);
+            let answers = if answers_path.exists() {
+                read(&answers_path, meta.length, meta.scheme, report)?
+            } else {
+                all.clone()
+            };
+
+            let mut list = WordList::new(meta.length, meta.scheme, all, answers);
+            list.retain_known_answers(&answers_path, report);
+            lists.insert(name, list);
+        }
+
+        let variants_path = data_dir().join(VARIANTS_FILE);
+        let variants = match fs::read_to_string(&variants_path) {
+            Ok(data) => VariantTable::parse(&data),
+            // without it traditional guesses are scored as wrong characters
+            Err(err) => {
+                let err = DictError::Read(variants_path.display().to_string(), err);
+                unreadable(&variants_path, err, report)?;
+                VariantTable::default()
+            }
+        };
+
+        let fuzzy_path = data_dir().join(FUZZY_FILE);
+        let fuzzy = match fs::read_to_string(&fuzzy_path) {
+            Ok(data) => match serde_json::from_str(&data) {
+                Ok(fuzzy) => fuzzy,
+                Err(err) => {
+                    let err = DictError::Parse(fuzzy_path.display().to_string(), err);
+                    unreadable(&fuzzy_path, err, report)?;
+                    FuzzyTable::default()
+                }
+            },
+            Err(_) => FuzzyTable::default(),
+        };
+
+        if mode == DictMode::Strict && !report.is_ok() {
+            return Err(DictError::Invalid(report.issues.len()));
+        }
+
+        Ok(Self {
+            lists,
+            variants,
+            fuzzy,
+        })
+    }
+
+    /// Looks up a list by name, falling back to `DEFAULT_LIST`.
+    pub fn list(&self, name: Option<&str>) -> Result<&WordList, DictError> {
+        let name = name.unwrap_or(DEFAULT_LIST);
+        self.lists
+            .get(name)
+            .ok_or_else(|| DictError::UnknownList(name.to_string()))
+    }
+}
+
+static DICTIONARY: OnceLock<Dictionary> = OnceLock::new();
+
+/// Makes `dictionary` the one returned by `get`. Panics if called twice.
+pub fn init(dictionary: Dictionary) {
+    DICTIONARY
+        .set(dictionary)
+        .unwrap_or_else(|_| panic!("dictionary initialized twice"));
+}
+
+/// The dictionary loaded at startup by `init`.
+pub fn get() -> &'static Dictionary {
+    DICTIONARY.get().expect("dictionary not initialized")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::variant::Script;
+
+    fn answer(word: &str, pinyin: &str) -> Answer {
+        Answer {
+            word: word.to_string(),
+            pinyin: pinyin.to_string(),
+            explanation: String::new(),
+            derivation: None,
+            example: None,
+            abbreviation: None,
+        }
+    }
+
+    #[test]
+    fn normalize_folds_only_unlisted_words() {
+        let variants = VariantTable::parse("干\t幹 乾\n后\t後\n");
+        let all = vec![
+            answer("乾坤未定", "qian2 kun1 wei4 ding4"),
+            answer("后来居上", "hou4 lai2 ju1 shang4"),
+        ];
+        let list = WordList::new(Some(4), Scheme::Pinyin, all, Vec::new());
+
+        assert_eq!(list.normalize("乾坤未定", &variants), "乾坤未定");
+        assert_eq!(list.normalize("後来居上", &variants), "后来居上");
+        assert_eq!(list.normalize("乾后", &variants), "干后");
+    }
+
+    /// Data files written under the test data directory, removed when
+    /// dropped.
+    struct TestFiles(Vec<PathBuf>);
+
+    impl TestFiles {
+        fn write(files: &[(&str, &str)]) -> Self {
+            let paths = files
+                .iter()
+                .map(|(name, data)| {
+                    let path = data_dir().join(name);
+                    fs::create_dir_all(path.parent().unwrap()).unwrap();
+                    fs::write(&path, data).unwrap();
+                    path
+                })
+                .collect();
+            Self(paths)
+        }
+    }
+
+    impl Drop for TestFiles {
+        fn drop(&mut self) {
+            for path in &self.0 {
+                let _ = fs::remove_file(path);
+                let dirs = path.ancestors().skip(1);
+                for dir in dirs.take_while(|dir| dir.starts_with(data_dir())) {
+                    if fs::remove_dir(dir).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn load_reports_bad_lists_and_missing_variants() {
+        let entry = |word, pinyin| serde_json::to_string(&[answer(word, pinyin)]).unwrap();
+        let _files = TestFiles::write(&[
+            (ALL_FILE, &entry("一心一意", "yi1 xin1 yi1 yi4")),
+            (ANSWERS_FILE, &entry("三心二意", "san1 xin1 er4 yi4")),
+            (
+                "lists/idiom/all.json",
+                &entry("乾坤未定", "qian2 kun1 wei4 ding4"),
+            ),
+        ]);
+
+        let mut report = ValidationReport::default();
+        let dictionary = Dictionary::load(DictMode::Degraded, &mut report).unwrap();
+        let kinds = report
+            .issues
+            .iter()
+            .map(|issue| &issue.kind)
+            .collect::<Vec<_>>();
+        assert!(matches!(
+            kinds[..],
+            [
+                IssueKind::UnknownAnswer,
+                IssueKind::Reserved,
+                IssueKind::Unreadable(DictError::Read(..))
+            ]
+        ));
+        let list = dictionary.list(None).unwrap();
+        assert!(list.answers.is_empty());
+        assert!(list.lookup("一心一意").is_some());
+
+        let res = Dictionary::load(DictMode::Strict, &mut ValidationReport::default());
+        assert!(matches!(res, Err(DictError::Read(..))));
+
+        let _variants = TestFiles::write(&[(VARIANTS_FILE, "干\t幹 乾\n")]);
+        let mut report = ValidationReport::default();
+        let dictionary = Dictionary::load(DictMode::Degraded, &mut report).unwrap();
+        assert_eq!(report.issues.len(), 2);
+        assert_eq!(dictionary.variants.convert("干", Script::Traditional), "幹");
+
+        let res = Dictionary::load(DictMode::Strict, &mut ValidationReport::default());
+        assert!(matches!(res, Err(DictError::Invalid(2))));
+    }
 }
@@ -1,27 +1,179 @@
+//! Error types, one per area, all convertible into `OmniError`.
+
+use serde::Serialize;
 use thiserror::Error;
+
+/// A syllable that cannot be parsed.
 #[derive(Debug, Error)]
 pub enum PinyinError {
+    /// The syllable is empty.
     #[error("invalid length (`{0}`): expected > 0")]
     InvalidLength(usize),
-    #[error("invalid tone (`{0}`): expected one of {{0, 1, 2, 3, 4}}, or nothing")]
-    InvalidTone(u8),
+    /// The tone digit is not one of the scheme's.
+    #[error("invalid tone (`{0}`): expected one of {{{1}}}, or nothing")]
+    InvalidTone(u8, &'static str),
+    /// The syllable has no known initial and vowel.
+    #[error("unrecognized reading (`{0}`)")]
+    UnrecognizedReading(String),
+    /// The scheme has no numbered syllables to parse.
+    #[error("unsupported scheme (`{0}`): expected a numbered-tone scheme")]
+    UnsupportedScheme(&'static str),
 }
 
+/// A word whose reading does not fit it.
 #[derive(Debug, Error)]
 pub enum IdiomError {
+    /// The word, the reading or both are not the expected length.
     #[error("inconsistent length (word: `{0}`, pinyin: `{1}`): expected {2}")]
     InconsistentLength(usize, usize, usize),
+    /// A syllable of the reading is invalid.
     #[error(transparent)]
     InvalidPinyin(#[from] PinyinError),
 
 }
 
+/// A dictionary that cannot be loaded, or a list that cannot be used.
+#[derive(Debug, Error)]
+pub enum DictError {
+    /// A file cannot be read.
+    #[error("failed to read `{0}`: {1}")]
+    Read(String, #[source] std::io::Error),
+    /// A file is not valid JSON of the expected shape.
+    #[error("failed to parse `{0}`: {1}")]
+    Parse(String, #[source] serde_json::Error),
+    /// Loading in strict mode found issues.
+    #[error("dictionary validation failed with {0} issue(s)")]
+    Invalid(usize),
+    /// No list has this name.
+    #[error("unknown word list `{0}`")]
+    UnknownList(String),
+    /// The list has nothing to draw an answer from.
+    #[error("the list has no answers")]
+    NoAnswers,
+}
+
+/// Why an uploaded custom list entry was rejected.
+#[derive(Debug, Serialize)]
+pub struct EntryError {
+    /// 0-based position of the entry in the upload.
+    pub index: usize,
+    /// Word of the entry.
+    pub word: String,
+    /// What is wrong with it.
+    pub error: String,
+}
+
+/// A custom list that cannot be stored or found.
+#[derive(Debug, Error)]
+pub enum GroupError {
+    /// The group id could not be a file name.
+    #[error("invalid group id (`{0}`): expected ASCII letters, digits, `-` or `_`")]
+    InvalidGroupId(String),
+    /// The list has no name.
+    #[error("invalid list name: expected non-empty")]
+    EmptyName,
+    /// The list has no entries.
+    #[error("invalid list: expected at least one entry")]
+    EmptyList,
+    /// The group has no list of this name.
+    #[error("unknown custom list `{0}`")]
+    UnknownList(String),
+    /// The caller may not change the group's lists.
+    #[error("`{0}` is not the admin of this group")]
+    NotAdmin(String),
+    /// The word is already an earlier entry of the same upload.
+    #[error("`{0}` is also entry {1}")]
+    DuplicateEntry(String, usize),
+    /// An entry has no reading and none is known.
+    #[error("`{0}` is not in the dictionary and has no pinyin")]
+    MissingPinyin(String),
+    /// Some entries are invalid, each reported.
+    #[error("{} invalid entries", .0.len())]
+    InvalidEntries(Vec<EntryError>),
+}
+
+/// A race request that cannot be served.
+#[derive(Debug, Error)]
+pub enum RaceError {
+    /// The race has no id.
+    #[error("invalid race id: expected non-empty")]
+    EmptyId,
+    /// The roster is empty.
+    #[error("a race needs at least one player")]
+    NoPlayers,
+    /// No race has this id.
+    #[error("unknown race `{0}`")]
+    UnknownRace(String),
+    /// The race is over.
+    #[error("race `{0}` is over")]
+    Finished(String),
+    /// The player has found the answer or run out of attempts.
+    #[error("player `{0}` has already finished")]
+    PlayerDone(String),
+    /// The player is not on the roster.
+    #[error("unknown player `{0}`")]
+    UnknownPlayer(String),
+    /// The player's board changed while the guess was scored.
+    #[error("player `{0}` guessed meanwhile")]
+    Conflict(String),
+    /// The guess has no reading and none is known.
+    #[error("`{0}` is not in the dictionary and has no pinyin")]
+    MissingPinyin(String),
+}
+
+/// A guess on a shared board that is not allowed.
+#[derive(Debug, Error)]
+pub enum CoopError {
+    /// A cooldown was asked for without a game.
+    #[error("a cooldown needs a `game_id`")]
+    MissingGameId,
+    /// A cooldown was asked for, but the newest attempt has no guesser.
+    #[error("a cooldown needs the guesser of the newest attempt")]
+    MissingGuesser,
+    /// The player guessed too recently; the seconds left are given.
+    #[error("player `{0}` must wait {1}s before guessing again")]
+    Cooldown(String, u64),
+}
+
+/// Player stats that cannot be read or stored.
+#[derive(Debug, Error)]
+pub enum StatsError {
+    /// The player id could not be a file name.
+    #[error("invalid player id (`{0}`): expected ASCII letters, digits, `-` or `_`")]
+    InvalidPlayerId(String),
+    /// The player has not finished a game.
+    #[error("no finished games for player `{0}`")]
+    UnknownPlayer(String),
+}
+
+/// Any error, as returned by the HTTP API.
 #[derive(Debug, Error)]
 pub enum OmniError {
+    /// See `IdiomError`.
     #[error(transparent)]
     Idiom(#[from] IdiomError),
+    /// See `DictError`.
+    #[error(transparent)]
+    Dict(#[from] DictError),
+    /// See `GroupError`.
+    #[error(transparent)]
+    Group(#[from] GroupError),
+    /// See `RaceError`.
+    #[error(transparent)]
+    Race(#[from] RaceError),
+    /// See `CoopError`.
+    #[error(transparent)]
+    Coop(#[from] CoopError),
+    /// See `StatsError`.
+    #[error(transparent)]
+    Stats(#[from] StatsError),
+    /// JSON that cannot be read or written.
     #[error(transparent)]
     SerdeJson(#[from] serde_json::Error),
+    /// A file that cannot be read or written.
     #[error(transparent)]
     Io(#[from] std::io::Error),
+    /// A command line that cannot be run.
+    #[error("{0}")]
+    Usage(String),
 }
@@ -0,0 +1,548 @@
+//! Scoring rules. Each `Mode` maps to an `Evaluator` that fills in the
+//! `CharacterResult` of every guessed character, consuming the answer's
+//! counts as it goes so a repeated guess is not credited twice.
+
+use crate::explain::{Dimension, Pass, Tracer};
+use crate::model::{CalculatedCharacter, CharInfo, Character, PinyinCount, ScoringOptions, State};
+use serde::Deserialize;
+
+/// A set of scoring rules.
+pub trait Evaluator: Sync {
+    /// Fills in the results of `guess` against `answer`, taking what it
+    /// credits out of `count` and recording its steps in `trace`.
+    fn evaluate(
+        &self,
+        answer: &[Character],
+        guess: &mut [CalculatedCharacter],
+        count: &mut PinyinCount,
+        options: &ScoringOptions,
+        trace: &mut dyn Tracer,
+    );
+}
+
+/// Which feedback a board gives.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Characters, pronunciations, initials, vowels and tones.
+    #[default]
+    Standard,
+    /// Pronunciations, initials, vowels and tones, but no character feedback.
+    Pronunciation,
+    /// Like `Standard` without tone feedback.
+    Toneless,
+    /// Characters only, like the original Wordle.
+    Classic,
+    /// Like `Standard`, but a matched character also uses up its
+    /// pronunciation, initial, vowel and tone.
+    Consuming,
+}
+
+impl Mode {
+    /// The rules of this mode.
+    pub fn evaluator(self) -> &'static dyn Evaluator {
+        match self {
+            Mode::Standard => &Standard,
+            Mode::Pronunciation => &PronunciationOnly,
+            Mode::Toneless => &Toneless,
+            Mode::Classic => &Classic,
+            Mode::Consuming => &Consuming,
+        }
+    }
+}
+
+struct Standard;
+
+impl Evaluator for Standard {
+    fn evaluate(
+        &self,
+        answer: &[Character],
+        guess: &mut [CalculatedCharacter],
+        count: &mut PinyinCount,
+        options: &ScoringOptions,
+        trace: &mut dyn Tracer,
+    ) {
+        score_whole(answer, guess, count, trace);
+        score_pronunciation(answer, guess, count, trace);
+        score_initial(answer, guess, count, options, trace);
+        score_vowel(answer, guess, count, options, trace);
+        score_tone(answer, guess, count, trace);
+    }
+}
+
+struct PronunciationOnly;
+
+impl Evaluator for PronunciationOnly {
+    fn evaluate(
+        &self,
+        answer: &[Character],
+        guess: &mut [CalculatedCharacter],
+        count: &mut PinyinCount,
+        options: &ScoringOptions,
+        trace: &mut dyn Tracer,
+    ) {
+        score_pronunciation(answer, guess, count, trace);
+        score_initial(answer, guess, count, options, trace);
+        score_vowel(answer, guess, count, options, trace);
+        score_tone(answer, guess, count, trace);
+    }
+}
+
+struct Toneless;
+
+impl Evaluator for Toneless {
+    fn evaluate(
+        &self,
+        answer: &[Character],
+        guess: &mut [CalculatedCharacter],
+        count: &mut PinyinCount,
+        options: &ScoringOptions,
+        trace: &mut dyn Tracer,
+    ) {
+        score_whole(answer, guess, count, trace);
+        score_pronunciation(answer, guess, count, trace);
+        score_initial(answer, guess, count, options, trace);
+        score_vowel(answer, guess, count, options, trace);
+    }
+}
+
+struct Classic;
+
+impl Evaluator for Classic {
+    fn evaluate(
+        &self,
+        answer: &[Character],
+        guess: &mut [CalculatedCharacter],
+        count: &mut PinyinCount,
+        _options: &ScoringOptions,
+        trace: &mut dyn Tracer,
+    ) {
+        score_whole(answer, guess, count, trace);
+    }
+}
+
+struct Consuming;
+
+impl Evaluator for Consuming {
+    fn evaluate(
+        &self,
+        answer: &[Character],
+        guess: &mut [CalculatedCharacter],
+        count: &mut PinyinCount,
+        options: &ScoringOptions,
+        trace: &mut dyn Tracer,
+    ) {
+        score_whole(answer, guess, count, trace);
+
+        // the parts of a matched character share its state and are no longer
+        // available to the other characters; parts it does not have, or whose
+        // counts are used up, are left to the passes below
+        for (i, char) in guess.iter_mut().enumerate() {
+            let state = char.result.whole;
+            if state == State::Missing {
+                continue;
+            }
+            let used = [
+                count.match_pronunciation_char(char),
+                count.match_initial_char(char),
+                count.match_vowel_char(char),
+                count.match_tone_char(char),
+            ];
+            let result = &mut char.result;
+            let parts = [
+                (Dimension::Pronunciation, &mut result.pronunciation),
+                (Dimension::Initial, &mut result.initial),
+                (Dimension::Vowel, &mut result.vowel),
+                (Dimension::Tone, &mut result.tone),
+            ];
+
+            let answer_position = trace.answer_position(i, Dimension::Whole);
+            for ((dimension, part), matched) in parts.into_iter().zip(used) {
+                if matched {
+                    *part = state;
+                    trace.record(i, dimension, state, Pass::Whole, answer_position);
+                }
+            }
+        }
+
+        score_pronunciation(answer, guess, count, trace);
+        score_initial(answer, guess, count, options, trace);
+        score_vowel(answer, guess, count, options, trace);
+        score_tone(answer, guess, count, trace);
+    }
+}
+
+fn score_whole(
+    answer: &[Character],
+    guess: &mut [CalculatedCharacter],
+    count: &mut PinyinCount,
+    trace: &mut dyn Tracer,
+) {
+    trace.scoring(Dimension::Whole);
+    for (i, (char, ans_char)) in guess.iter_mut().zip(answer).enumerate() {
+        if char.result.whole == State::Missing && char.literal() == ans_char.literal() {
+            let matched = count.match_whole_char(char);
+            char.result.whole = State::Correct;
+            trace.record(
+                i,
+                Dimension::Whole,
+                State::Correct,
+                Pass::Exact,
+                matched.then_some(i),
+            );
+        }
+    }
+    for (i, char) in guess.iter_mut().enumerate() {
+        if char.result.whole != State::Missing {
+            continue;
+        }
+        if count.literals.contains_key(&char.literal()) && count.match_whole_char(char) {
+            char.result.whole = State::Misplaced;
+            trace.claim(
+                i,
+                Dimension::Whole,
+                State::Misplaced,
+                Pass::Misplaced,
+                answer,
+                &|| char.literal(),
+            );
+        }
+    }
+}
+
+fn score_pronunciation(
+    answer: &[Character],
+    guess: &mut [CalculatedCharacter],
+    count: &mut PinyinCount,
+    trace: &mut dyn Tracer,
+) {
+    trace.scoring(Dimension::Pronunciation);
+    for (i, (char, ans_char)) in guess.iter_mut().zip(answer).enumerate() {
+        if char.result.pronunciation == State::Missing
+            && char.pronunciation() == ans_char.pronunciation()
+        {
+            let matched = count.match_pronunciation_char(char);
+            char.result.pronunciation = State::Correct;
+            trace.record(
+                i,
+                Dimension::Pronunciation,
+                State::Correct,
+                Pass::Exact,
+                matched.then_some(i),
+            );
+        }
+    }
+    for (i, char) in guess.iter_mut().enumerate() {
+        if char.result.pronunciation != State::Missing {
+            continue;
+        }
+        if count.pronunciations.contains_key(&char.pronunciation())
+            && count.match_pronunciation_char(char)
+        {
+            char.result.pronunciation = State::Misplaced;
+            trace.claim(
+                i,
+                Dimension::Pronunciation,
+                State::Misplaced,
+                Pass::Misplaced,
+                answer,
+                &|| char.pronunciation(),
+            );
+        }
+    }
+}
+
+fn score_initial(
+    answer: &[Character],
+    guess: &mut [CalculatedCharacter],
+    count: &mut PinyinCount,
+    options: &ScoringOptions,
+    trace: &mut dyn Tracer,
+) {
+    trace.scoring(Dimension::Initial);
+    for (i, (char, ans_char)) in guess.iter_mut().zip(answer).enumerate() {
+        if char.result.initial == State::Missing && char.initial() == ans_char.initial() {
+            let matched = count.match_initial_char(char);
+            char.result.initial = State::Correct;
+            trace.record(
+                i,
+                Dimension::Initial,
+                State::Correct,
+                Pass::Exact,
+                matched.then_some(i),
+            );
+        }
+    }
+    for (i, char) in guess.iter_mut().enumerate() {
+        if char.result.initial != State::Missing {
+            continue;
+        }
+        if let Some(initial) = char.initial().0
+            && count.initials.contains_key(&initial)
+            && count.match_initial_char(char)
+        {
+            char.result.initial = State::Misplaced;
+            trace.claim(
+                i,
+                Dimension::Initial,
+                State::Misplaced,
+                Pass::Misplaced,
+                answer,
+                &|| initial.clone(),
+            );
+        }
+    }
+    if let Some(fuzzy) = options.fuzzy {
+        for (i, (char, ans_char)) in guess.iter_mut().zip(answer).enumerate() {
+            if char.result.initial != State::Missing {
+                continue;
+            }
+            if let (Some(initial), Some(ans_initial)) = (char.initial().0, ans_char.initial().0)
+                && fuzzy.initials_near(&initial, &ans_initial)
+                && count.match_initial(ans_initial.clone())
+            {
+                char.result.initial = State::Near;
+                trace.claim(
+                    i,
+                    Dimension::Initial,
+                    State::Near,
+                    Pass::Fuzzy,
+                    answer,
+                    &|| ans_initial.clone(),
+                );
+            }
+        }
+    }
+}
+
+fn score_vowel(
+    answer: &[Character],
+    guess: &mut [CalculatedCharacter],
+    count: &mut PinyinCount,
+    options: &ScoringOptions,
+    trace: &mut dyn Tracer,
+) {
+    trace.scoring(Dimension::Vowel);
+    for (i, (char, ans_char)) in guess.iter_mut().zip(answer).enumerate() {
+        if char.result.vowel == State::Missing
+            && char.vowel().is_some()
+            && char.vowel() == ans_char.vowel()
+        {
+            let matched = count.match_vowel_char(char);
+            char.result.vowel = State::Correct;
+            trace.record(
+                i,
+                Dimension::Vowel,
+                State::Correct,
+                Pass::Exact,
+                matched.then_some(i),
+            );
+        }
+    }
+    for (i, char) in guess.iter_mut().enumerate() {
+        if char.result.vowel != State::Missing {
+            continue;
+        }
+        if let Some(vowel) = char.vowel()
+            && count.vowels.contains_key(&vowel)
+            && count.match_vowel_char(char)
+        {
+            char.result.vowel = State::Misplaced;
+            trace.claim(
+                i,
+                Dimension::Vowel,
+                State::Misplaced,
+                Pass::Misplaced,
+                answer,
+                &|| vowel.clone(),
+            );
+        }
+    }
+    if let Some(fuzzy) = options.fuzzy {
+        for (i, (char, ans_char)) in guess.iter_mut().zip(answer).enumerate() {
+            if char.result.vowel != State::Missing {
+                continue;
+            }
+            if let (Some(vowel), Some(ans_vowel)) = (char.vowel(), ans_char.vowel())
+                && fuzzy.vowels_near(&vowel, &ans_vowel)
+                && count.match_vowel(ans_vowel.clone())
+            {
+                char.result.vowel = State::Near;
+                trace.claim(
+                    i,
+                    Dimension::Vowel,
+                    State::Near,
+                    Pass::Fuzzy,
+                    answer,
+                    &|| ans_vowel.clone(),
+                );
+            }
+        }
+    }
+}
+
+fn score_tone(
+    answer: &[Character],
+    guess: &mut [CalculatedCharacter],
+    count: &mut PinyinCount,
+    trace: &mut dyn Tracer,
+) {
+    trace.scoring(Dimension::Tone);
+    // toneless syllables, such as kana readings, get no tone feedback
+    for (i, (char, ans_char)) in guess.iter_mut().zip(answer).enumerate() {
+        if char.result.tone == State::Missing
+            && char.tone().0.is_some()
+            && char.tone() == ans_char.tone()
+        {
+            let matched = count.match_tone_char(char);
+            char.result.tone = State::Correct;
+            trace.record(
+                i,
+                Dimension::Tone,
+                State::Correct,
+                Pass::Exact,
+                matched.then_some(i),
+            );
+        }
+    }
+    for (i, char) in guess.iter_mut().enumerate() {
+        if char.result.tone != State::Missing {
+            continue;
+        }
+        if let Some(tone) = char.tone().0
+            && count.tones.contains_key(&tone)
+            && count.match_tone_char(char)
+        {
+            char.result.tone = State::Misplaced;
+            trace.claim(
+                i,
+                Dimension::Tone,
+                State::Misplaced,
+                Pass::Misplaced,
+                answer,
+                &|| tone.number().to_string(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{Explanation, Trace};
+    use crate::model::{Attempt, CalculatedAttempt, CharacterResult, Scheme};
+
+    fn attempt(word: &str, pinyin: &str) -> Attempt {
+        Attempt {
+            word: word.to_string(),
+            pinyin: pinyin.to_string(),
+            verified: None,
+            guesser: None,
+        }
+    }
+
+    fn score(answer: &Attempt, guess: &Attempt, options: &ScoringOptions) -> Vec<CharacterResult> {
+        let answer = answer.to_characters(4, Scheme::Pinyin).unwrap();
+        CalculatedAttempt::from_attempt(&answer, guess, options)
+            .unwrap()
+            .characters
+            .into_iter()
+            .map(|char| char.result)
+            .collect()
+    }
+
+    #[test]
+    fn repeated_guess_credited_once() {
+        let answer = attempt("一心一意", "yi1 xin1 yi1 yi4");
+        let guess = attempt("意意意意", "yi4 yi4 yi4 yi4");
+        let res = score(&answer, &guess, &ScoringOptions::default());
+
+        assert_eq!(res[3].whole, State::Correct);
+        assert_eq!(res[3].tone, State::Correct);
+        assert!(
+            res[..3]
+                .iter()
+                .all(|r| r.whole == State::Missing && r.tone == State::Missing)
+        );
+    }
+
+    #[test]
+    fn consuming_copies_only_parts_with_counts_left() {
+        let answer = attempt("长长久久", "chang2 zhang3 jiu3 jiu3");
+        let guess = attempt("长长久久", "chang3 chang3 jiu3 jiu3");
+        let options = ScoringOptions {
+            mode: Mode::Consuming,
+            ..Default::default()
+        };
+        let res = score(&answer, &guess, &options);
+
+        assert_eq!(res[0].pronunciation, State::Correct);
+        assert_eq!(res[1].whole, State::Correct);
+        assert_eq!(res[1].pronunciation, State::Missing);
+        assert_eq!(res[1].initial, State::Missing);
+        assert_eq!(res[1].vowel, State::Correct);
+    }
+
+    #[test]
+    fn classic_scores_characters_only() {
+        let answer = attempt("一心一意", "yi1 xin1 yi1 yi4");
+        let guess = attempt("心一意一", "xin1 yi1 yi4 yi1");
+        let options = ScoringOptions {
+            mode: Mode::Classic,
+            ..Default::default()
+        };
+
+        for res in score(&answer, &guess, &options) {
+            assert_eq!(res.whole, State::Misplaced);
+            assert_eq!(res.pronunciation, State::Missing);
+            assert_eq!(res.tone, State::Missing);
+        }
+    }
+
+    #[test]
+    fn consuming_uses_up_matched_parts() {
+        let answer = attempt("一心一意", "yi1 xin1 yi1 yi4");
+        let guess = attempt("衣一衣衣", "yi1 yi1 yi1 yi1");
+        let standard = score(&answer, &guess, &ScoringOptions::default());
+        let consuming = score(
+            &answer,
+            &guess,
+            &ScoringOptions {
+                mode: Mode::Consuming,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(standard[1].whole, State::Misplaced);
+        assert_eq!(standard[1].pronunciation, State::Missing);
+        assert_eq!(consuming[1].whole, State::Misplaced);
+        assert_eq!(consuming[1].pronunciation, State::Misplaced);
+    }
+
+    #[test]
+    fn trace_claims_only_decremented_counts() {
+        let answer = attempt("东西南北", "dong1 xi0 nan2 bei3")
+            .to_characters(4, Scheme::Pinyin)
+            .unwrap();
+        let guess = attempt("来吧走吧", "lai2 ba0 zou3 ba0");
+        let options = ScoringOptions {
+            legacy_neutral_tone: true,
+            ..Default::default()
+        };
+
+        let mut trace = Trace::default();
+        let res = CalculatedAttempt::traced(&answer, &guess, &options, &mut trace).unwrap();
+        let steps = Explanation::new(&res, &trace).steps;
+        let tone = |position| {
+            steps
+                .iter()
+                .find(|step| step.position == position && step.dimension == Dimension::Tone)
+                .unwrap()
+        };
+
+        assert_eq!(tone(1).state, State::Correct);
+        assert_eq!(tone(1).answer_position, None);
+        assert_eq!(tone(0).state, State::Misplaced);
+        assert_eq!(tone(0).answer_position, Some(2));
+    }
+}
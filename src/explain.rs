@@ -0,0 +1,295 @@
+//! Scoring traces, for explaining why a cell got its color. The evaluator
+//! records which pass decided each state and which answer position's count
+//! that pass used up.
+
+use crate::model::{CalculatedAttempt, CalculatedCharacter, CharInfo, Character, State};
+use serde::Serialize;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// Part of a character that gets its own state.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dimension {
+    /// The character itself.
+    Whole,
+    /// The syllable without its tone.
+    Pronunciation,
+    /// The initial.
+    Initial,
+    /// The vowel, or final.
+    Vowel,
+    /// The tone.
+    Tone,
+}
+
+const DIMENSIONS: [Dimension; 5] = [
+    Dimension::Whole,
+    Dimension::Pronunciation,
+    Dimension::Initial,
+    Dimension::Vowel,
+    Dimension::Tone,
+];
+
+impl Dimension {
+    fn key(self, character: &impl CharInfo) -> Option<String> {
+        match self {
+            Dimension::Whole => Some(character.literal()),
+            Dimension::Pronunciation => Some(character.pronunciation()),
+            Dimension::Initial => character.initial().0,
+            Dimension::Vowel => character.vowel(),
+            Dimension::Tone => character.tone().0.map(|tone| tone.number().to_string()),
+        }
+    }
+}
+
+impl Display for Dimension {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Dimension::Whole => "whole",
+                Dimension::Pronunciation => "pronunciation",
+                Dimension::Initial => "initial",
+                Dimension::Vowel => "vowel",
+                Dimension::Tone => "tone",
+            }
+        )
+    }
+}
+
+/// How a state was decided.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Pass {
+    /// Same value at the same position.
+    Exact,
+    /// Same value at another position with a count left.
+    Misplaced,
+    /// Fuzzy-equivalent value at the same position.
+    Fuzzy,
+    /// Used up together with a matched character, `Mode::Consuming` only.
+    Whole,
+    /// No pass matched: the value is in the answer, but its counts were used
+    /// up by other characters.
+    Exhausted,
+    /// No pass matched: the value is not in the answer.
+    Absent,
+    /// The mode does not score this dimension.
+    Skipped,
+}
+
+impl Display for Pass {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Pass::Exact => "same position",
+                Pass::Misplaced => "another position",
+                Pass::Fuzzy => "fuzzy match",
+                Pass::Whole => "used up with the character",
+                Pass::Exhausted => "in the answer, but already used up",
+                Pass::Absent => "not in the answer",
+                Pass::Skipped => "not scored in this mode",
+            }
+        )
+    }
+}
+
+/// The decision of one state.
+#[derive(Clone, Debug, Serialize)]
+pub struct Step {
+    /// 0-based position in the guess.
+    pub position: usize,
+    /// Part of the character decided.
+    pub dimension: Dimension,
+    /// State it was given.
+    pub state: State,
+    /// Pass that decided it.
+    pub pass: Pass,
+    /// 0-based answer position whose count was used up.
+    pub answer_position: Option<usize>,
+}
+
+/// Told how every state was decided while scoring. Plain scoring uses
+/// `NoTrace`, whose calls do nothing.
+pub trait Tracer {
+    /// Marks `dimension` as scored by the mode.
+    fn scoring(&mut self, _dimension: Dimension) {}
+
+    /// Records the decision of one state.
+    fn record(
+        &mut self,
+        _position: usize,
+        _dimension: Dimension,
+        _state: State,
+        _pass: Pass,
+        _answer_position: Option<usize>,
+    ) {
+    }
+
+    /// Records a match against the first answer position holding `key` whose
+    /// count has not been used up yet.
+    fn claim(
+        &mut self,
+        _position: usize,
+        _dimension: Dimension,
+        _state: State,
+        _pass: Pass,
+        _answer: &[Character],
+        _key: &dyn Fn() -> String,
+    ) {
+    }
+
+    /// Answer position recorded for a cell, if any.
+    fn answer_position(&self, _position: usize, _dimension: Dimension) -> Option<usize> {
+        None
+    }
+
+    /// Fills in why every undecided cell stayed missing.
+    fn finish(&mut self, _answer: &[Character], _guess: &[CalculatedCharacter]) {}
+}
+
+/// Records nothing.
+pub struct NoTrace;
+
+impl Tracer for NoTrace {}
+
+/// Collects `Step`s while scoring.
+#[derive(Debug, Default)]
+pub struct Trace {
+    scored: Vec<Dimension>,
+    steps: Vec<Step>,
+}
+
+impl Tracer for Trace {
+    fn scoring(&mut self, dimension: Dimension) {
+        self.scored.push(dimension);
+    }
+
+    fn record(
+        &mut self,
+        position: usize,
+        dimension: Dimension,
+        state: State,
+        pass: Pass,
+        answer_position: Option<usize>,
+    ) {
+        self.steps.push(Step {
+            position,
+            dimension,
+            state,
+            pass,
+            answer_position,
+        });
+    }
+
+    fn claim(
+        &mut self,
+        position: usize,
+        dimension: Dimension,
+        state: State,
+        pass: Pass,
+        answer: &[Character],
+        key: &dyn Fn() -> String,
+    ) {
+        let key = key();
+        let answer_position = answer.iter().enumerate().position(|(i, character)| {
+            dimension.key(character).as_deref() == Some(key.as_str())
+                && !self
+                    .steps
+                    .iter()
+                    .any(|step| step.dimension == dimension && step.answer_position == Some(i))
+        });
+        self.record(position, dimension, state, pass, answer_position);
+    }
+
+    fn answer_position(&self, position: usize, dimension: Dimension) -> Option<usize> {
+        self.steps
+            .iter()
+            .find(|step| step.position == position && step.dimension == dimension)
+            .and_then(|step| step.answer_position)
+    }
+
+    fn finish(&mut self, answer: &[Character], guess: &[CalculatedCharacter]) {
+        for (position, character) in guess.iter().enumerate() {
+            for dimension in DIMENSIONS {
+                if self
+                    .steps
+                    .iter()
+                    .any(|step| step.position == position && step.dimension == dimension)
+                {
+                    continue;
+                }
+
+                let key = dimension.key(character);
+                let pass = if !self.scored.contains(&dimension) {
+                    Pass::Skipped
+                } else if key.is_some() && answer.iter().any(|ans| dimension.key(ans) == key) {
+                    Pass::Exhausted
+                } else {
+                    Pass::Absent
+                };
+                self.record(position, dimension, State::Missing, pass, None);
+            }
+        }
+
+        self.steps.sort_by_key(|step| {
+            (
+                step.position,
+                DIMENSIONS.iter().position(|d| *d == step.dimension),
+            )
+        });
+    }
+}
+
+/// A scored guess with the steps that decided it.
+#[derive(Debug, Serialize)]
+pub struct Explanation {
+    /// The guess, in the script it was scored in.
+    pub word: String,
+    /// Its reading, syllables separated by spaces.
+    pub pinyin: String,
+    /// By position, then in `Dimension` order.
+    pub steps: Vec<Step>,
+}
+
+impl Explanation {
+    /// The explanation of `attempt`, scored with `trace`.
+    pub fn new(attempt: &CalculatedAttempt, trace: &Trace) -> Self {
+        Self {
+            word: attempt.characters.iter().map(CharInfo::literal).collect(),
+            pinyin: attempt
+                .characters
+                .iter()
+                .map(|c| format!("{}{}", c.pronunciation(), c.tone()))
+                .collect::<Vec<_>>()
+                .join(" "),
+            steps: trace.steps.clone(),
+        }
+    }
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(f, "{} ({})", self.word, self.pinyin)?;
+        for step in &self.steps {
+            write!(
+                f,
+                "  #{} {:<13} {:<9} {}",
+                step.position + 1,
+                step.dimension.to_string(),
+                step.state.to_string(),
+                step.pass
+            )?;
+            if let Some(answer_position) = step.answer_position {
+                write!(f, ", answer #{}", answer_position + 1)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
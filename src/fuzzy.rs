@@ -0,0 +1,93 @@
+//! Fuzzy-pinyin equivalences for dialect-friendly play. An initial or vowel
+//! that is equivalent to the answer's at the same position is scored
+//! `State::Near` instead of `State::Missing`.
+//!
+//! The table can be overridden with `data/fuzzy.json`:
+//!
+//! ```json
+//! { "initials": [["zh", "z"], ["n", "l"]], "vowels": [["an", "ang"]] }
+//! ```
+
+use serde::Deserialize;
+
+/// File under `data/` overriding the default table.
+pub const FUZZY_FILE: &str = "fuzzy.json";
+
+/// Pairs of initials and of vowels that count as near each other, either way
+/// round.
+#[derive(Clone, Debug, Deserialize)]
+pub struct FuzzyTable {
+    /// Initials that are near each other, such as `zh` and `z`.
+    #[serde(default)]
+    pub initials: Vec<(String, String)>,
+    /// Vowels that are near each other, such as `an` and `ang`.
+    #[serde(default)]
+    pub vowels: Vec<(String, String)>,
+}
+
+impl Default for FuzzyTable {
+    /// The usual southern confusions: zh/z, ch/c, sh/s, n/l, an/ang, en/eng
+    /// and in/ing.
+    fn default() -> Self {
+        let pairs = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(a, b)| (a.to_string(), b.to_string()))
+                .collect()
+        };
+
+        Self {
+            initials: pairs(&[("zh", "z"), ("ch", "c"), ("sh", "s"), ("n", "l")]),
+            vowels: pairs(&[
+                ("an", "ang"),
+                ("en", "eng"),
+                ("in", "ing"),
+                ("ian", "iang"),
+                ("uan", "uang"),
+            ]),
+        }
+    }
+}
+
+fn is_near(pairs: &[(String, String)], a: &str, b: &str) -> bool {
+    pairs
+        .iter()
+        .any(|(x, y)| (x == a && y == b) || (x == b && y == a))
+}
+
+impl FuzzyTable {
+    /// Whether initials `a` and `b` are listed as equivalent.
+    pub fn initials_near(&self, a: &str, b: &str) -> bool {
+        is_near(&self.initials, a, b)
+    }
+
+    /// Whether vowels `a` and `b` are listed as equivalent.
+    pub fn vowels_near(&self, a: &str, b: &str) -> bool {
+        is_near(&self.vowels, a, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs_match_either_way() {
+        let table = FuzzyTable::default();
+
+        assert!(table.initials_near("zh", "z"));
+        assert!(table.initials_near("z", "zh"));
+        assert!(table.vowels_near("ang", "an"));
+        assert!(!table.initials_near("zh", "ch"));
+        assert!(!table.vowels_near("an", "an"));
+    }
+
+    #[test]
+    fn override_defaults_missing_kinds_to_none() {
+        let table: FuzzyTable = serde_json::from_str(r#"{ "initials": [["f", "h"]] }"#).unwrap();
+
+        assert!(table.initials_near("h", "f"));
+        assert!(!table.initials_near("zh", "z"));
+        assert!(table.vowels.is_empty());
+    }
+}
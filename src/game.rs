@@ -1,14 +1,48 @@
-use crate::constant::{IDIOM_LENGTH, INITIALS, SPECIAL_INITIALS};
+//! Reading parsing and scoring: `Pinyin::parse`, `Attempt::to_characters`
+//! and `CalculatedAttempt::from_attempt`.
+
+use crate::constant::{INITIALS, JYUTPING_INITIALS, SPECIAL_INITIALS};
 use crate::error::{IdiomError, PinyinError};
+use crate::explain::{NoTrace, Trace, Tracer};
+use crate::fuzzy::FuzzyTable;
 use crate::model::*;
+use crate::sandhi::{self, Sandhi};
+use crate::{kana, zhuyin};
 
 impl TryFrom<&str> for Pinyin {
     type Error = PinyinError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::parse(value, Scheme::Pinyin)
+    }
+}
+
+impl Pinyin {
+    /// Parses a numbered-tone syllable (`jian1`, `gin1`) written in `scheme`.
+    /// Pinyin may also be typed in Zhuyin (`ㄐㄧㄢ`).
+    pub fn parse(value: &str, scheme: Scheme) -> Result<Self, PinyinError> {
         let mut chars: Vec<char> = value.chars().collect();
         if chars.is_empty() {
-            return Err(Self::Error::InvalidLength(chars.len()));
+            return Err(PinyinError::InvalidLength(chars.len()));
+        }
+
+        if scheme == Scheme::Pinyin && zhuyin::is_zhuyin(value) {
+            let pinyin = zhuyin::to_pinyin(value)
+                .ok_or_else(|| PinyinError::UnrecognizedReading(value.to_string()))?;
+            return Self::parse(&pinyin, scheme);
+        }
+
+        if scheme == Scheme::Kana {
+            let kana = kana::to_hiragana(value)
+                .ok_or_else(|| PinyinError::UnrecognizedReading(value.to_string()))?;
+            let mut morae = kana::morae(&kana).into_iter();
+
+            return Ok(Pinyin {
+                initial: Initial(morae.next()),
+                vowel: morae.collect(),
+                pronunciation: kana,
+                tone: Tone(None),
+            });
         }
 
         let tone = {
@@ -16,25 +50,36 @@ impl TryFrom<&str> for Pinyin {
             if tone_num.is_some() {
                 chars.pop();
             }
-            Tone::from_num(tone_num)?
+            Tone::from_num(tone_num, scheme)?
         };
 
         let pronunciation = chars.iter().collect::<String>();
 
-        let initial = {
-            let mut initial = chars.first().unwrap().to_string();
-            if INITIALS.contains(&initial) {
-                if SPECIAL_INITIALS.contains(&initial) && chars.get(1) == Some(&'h') {
-                    initial.push('h');
-                }
-
-                chars = chars.split_off(initial.len());
+        let initial = match scheme {
+            Scheme::Pinyin => {
+                let mut initial = chars.first().unwrap().to_string();
+                if INITIALS.contains(&initial) {
+                    if SPECIAL_INITIALS.contains(&initial) && chars.get(1) == Some(&'h') {
+                        initial.push('h');
+                    }
 
-                Some(initial)
-            } else {
-                None
+                    Some(initial)
+                } else {
+                    None
+                }
             }
+            // kana readings are split into morae above
+            Scheme::Kana => return Err(PinyinError::UnsupportedScheme("kana")),
+            // syllabic nasals (`m4`, `ng5`) are all final
+            Scheme::Jyutping => JYUTPING_INITIALS
+                .iter()
+                .find(|initial| pronunciation.starts_with(*initial))
+                .filter(|initial| pronunciation.len() > initial.len())
+                .map(ToString::to_string),
         };
+        if let Some(initial) = &initial {
+            chars = chars.split_off(initial.len());
+        }
 
         Ok(Pinyin {
             pronunciation,
@@ -45,41 +90,55 @@ impl TryFrom<&str> for Pinyin {
     }
 }
 
-impl TryFrom<Attempt> for [Character; IDIOM_LENGTH] {
-    type Error = IdiomError;
+impl From<&Answer> for Attempt {
+    fn from(answer: &Answer) -> Self {
+        Self {
+            word: answer.word.clone(),
+            pinyin: answer.pinyin.clone(),
+            verified: None,
+            guesser: None,
+        }
+    }
+}
 
-    fn try_from(attempt: Attempt) -> Result<Self, Self::Error> {
-        let words = attempt.word.chars().collect::<Vec<_>>();
+impl Attempt {
+    /// Splits the attempt into characters, requiring both the word and the
+    /// pinyin to be exactly `length` long.
+    pub fn to_characters(
+        &self,
+        length: usize,
+        scheme: Scheme,
+    ) -> Result<Vec<Character>, IdiomError> {
+        let words = self.word.chars().collect::<Vec<_>>();
 
-        let pinyin_s = attempt
+        let pinyin_s = self
             .pinyin
             .split_whitespace()
-            .map(TryFrom::try_from)
+            .map(|pinyin| Pinyin::parse(pinyin, scheme))
             .collect::<Result<Vec<_>, _>>()?;
 
-        if words.len() != pinyin_s.len() || words.len() != IDIOM_LENGTH {
+        if words.len() != pinyin_s.len() || words.len() != length {
             Err(IdiomError::InconsistentLength(
                 words.len(),
                 pinyin_s.len(),
-                IDIOM_LENGTH,
+                length,
             ))?
         }
 
-        let characters = words
+        Ok(words
             .into_iter()
             .zip(pinyin_s)
             .map(|(literal, pinyin)| Character {
                 literal: literal.to_string(),
                 pinyin,
             })
-            .collect::<Vec<_>>();
-
-        Ok(characters.try_into().unwrap())
+            .collect())
     }
 }
 
 impl PinyinCount {
-    pub fn from_attempt(attempt: &[Character; IDIOM_LENGTH]) -> Self {
+    /// Counts the literals, initials, vowels, tones and syllables of an answer.
+    pub fn from_attempt(attempt: &[Character]) -> Self {
         let mut count: PinyinCount = Default::default();
 
         for character in attempt {
@@ -94,16 +153,12 @@ impl PinyinCount {
                 *count.initials.entry(initial).or_insert(0) += 1;
             }
 
-            *count
-                .vowels
-                .entry(character.pinyin.vowel.clone())
-                .or_insert(0) += 1;
+            if let Some(vowel) = character.vowel() {
+                *count.vowels.entry(vowel).or_insert(0) += 1;
+            }
 
-            if character.pinyin.tone.0.is_some() {
-                *count
-                    .tones
-                    .entry(character.pinyin.tone.to_string())
-                    .or_insert(0) += 1;
+            if let Some(tone) = character.pinyin.tone.0 {
+                *count.tones.entry(tone).or_insert(0) += 1;
             }
         }
 
@@ -116,6 +171,8 @@ impl From<Character> for CalculatedCharacter {
         Self {
             literal: character.literal,
             pinyin: character.pinyin,
+            zhuyin: None,
+            spoken_tone: None,
             result: Default::default(),
         }
     }
@@ -126,112 +183,217 @@ impl From<&Character> for CalculatedCharacter {
         Self {
             literal: character.literal.clone(),
             pinyin: character.pinyin.clone(),
+            zhuyin: None,
+            spoken_tone: None,
             result: Default::default(),
         }
     }
 }
 
+impl ScoringOptions<'_> {
+    /// Sandhi only applies to Mandarin.
+    pub fn effective_sandhi(&self) -> Sandhi {
+        match self.scheme {
+            Scheme::Pinyin => self.sandhi,
+            _ => Sandhi::Off,
+        }
+    }
+}
+
+impl Rules {
+    /// The scoring options these rules amount to for a list in `scheme`.
+    pub fn options<'a>(&self, scheme: Scheme, fuzzy: &'a FuzzyTable) -> ScoringOptions<'a> {
+        ScoringOptions {
+            scheme,
+            fuzzy: self.fuzzy.then_some(fuzzy),
+            legacy_neutral_tone: self.legacy_neutral_tone,
+            sandhi: self.sandhi,
+            mode: self.mode,
+        }
+    }
+}
+
+/// Scores each pair on its own, without rendering. Answers are `length`
+/// characters long, or as long as their word if `None`; a malformed pair only
+/// fails its own item.
+pub fn evaluate(
+    pairs: &[Pair],
+    length: Option<usize>,
+    options: &ScoringOptions,
+) -> Vec<Result<CalculatedAttempt, IdiomError>> {
+    pairs
+        .iter()
+        .map(|pair| {
+            let length = length.unwrap_or_else(|| pair.answer.word.chars().count());
+            let answer = pair.answer.to_characters(length, options.scheme)?;
+            CalculatedAttempt::from_attempt(&answer, &pair.guess, options)
+        })
+        .collect()
+}
+
 impl CalculatedAttempt {
+    /// Scores `attempt` against the parsed `answer`.
     pub fn from_attempt(
-        answer: &[Character; IDIOM_LENGTH],
+        answer: &[Character],
         attempt: &Attempt,
+        options: &ScoringOptions,
     ) -> Result<Self, IdiomError> {
-        let mut answer_count = PinyinCount::from_attempt(answer);
+        Self::score(answer, attempt, options, &mut NoTrace)
+    }
 
-        let characters: [Character; IDIOM_LENGTH] = attempt.clone().try_into()?;
-        let mut res: [CalculatedCharacter; IDIOM_LENGTH] = characters.map(Into::into);
+    /// Like `from_attempt`, recording into `trace` how each state was decided.
+    pub fn traced(
+        answer: &[Character],
+        attempt: &Attempt,
+        options: &ScoringOptions,
+        trace: &mut Trace,
+    ) -> Result<Self, IdiomError> {
+        Self::score(answer, attempt, options, trace)
+    }
 
-        for (char, ans_char) in res.iter_mut().zip(answer) {
-            if char.literal() == ans_char.literal() {
-                answer_count.match_whole_char(char);
-                char.result.whole = State::Correct;
-            }
-        }
-        for char in &mut res {
-            if char.result.whole != State::Missing {
-                continue;
-            }
-            if answer_count.literals.contains_key(&char.literal())
-                && answer_count.match_whole_char(char)
-            {
-                char.result.whole = State::Misplaced;
-            }
-        }
+    fn score(
+        answer: &[Character],
+        attempt: &Attempt,
+        options: &ScoringOptions,
+        trace: &mut dyn Tracer,
+    ) -> Result<Self, IdiomError> {
+        let sandhi = options.effective_sandhi();
+        let spoken_answer;
+        let answer = if sandhi == Sandhi::Off {
+            answer
+        } else {
+            spoken_answer = answer
+                .iter()
+                .zip(sandhi::spoken_tones(answer, sandhi))
+                .map(|(character, tone)| {
+                    let mut character = character.clone();
+                    character.pinyin.tone = tone;
+                    character
+                })
+                .collect::<Vec<_>>();
+            &spoken_answer
+        };
 
-        for (char, ans_char) in res.iter_mut().zip(answer) {
-            if char.pronunciation() == ans_char.pronunciation() {
-                answer_count.match_pronunciation_char(char);
-                char.result.pronunciation = State::Correct;
-            }
-        }
-        for char in &mut res {
-            if char.result.pronunciation != State::Missing {
-                continue;
-            }
-            if answer_count
-                .pronunciations
-                .contains_key(&char.pronunciation())
-                && answer_count.match_pronunciation_char(char)
-            {
-                char.result.pronunciation = State::Misplaced;
-            }
+        let mut answer_count = PinyinCount::from_attempt(answer);
+        if options.legacy_neutral_tone {
+            answer_count
+                .tones
+                .remove(&ToneExplicit::Mandarin(MandarinTone::Neutral));
         }
 
-        for (char, ans_char) in res.iter_mut().zip(answer) {
-            if char.initial() == ans_char.initial() {
-                answer_count.match_initial_char(char);
-                char.result.initial = State::Correct;
-            }
-        }
-        for char in &mut res {
-            if char.result.initial != State::Missing {
-                continue;
-            }
-            if let Some(initial) = char.initial().0 {
-                if answer_count.initials.contains_key(&initial)
-                    && answer_count.match_initial_char(char)
-                {
-                    char.result.initial = State::Misplaced;
-                }
-            }
-        }
+        let characters = attempt.to_characters(answer.len(), options.scheme)?;
+        let mut res: Vec<CalculatedCharacter> = characters.into_iter().map(Into::into).collect();
 
-        for (char, ans_char) in res.iter_mut().zip(answer) {
-            if char.vowel() == ans_char.vowel() {
-                answer_count.match_vowel_char(char);
-                char.result.vowel = State::Correct;
-            }
-        }
-        for char in &mut res {
-            if char.result.vowel != State::Missing {
-                continue;
-            }
-            if answer_count.vowels.contains_key(&char.vowel())
-                && answer_count.match_vowel_char(char)
-            {
-                char.result.vowel = State::Misplaced;
-            }
+        // score with the spoken tones, the citation tones are put back below
+        let citation_tones = res.iter().map(CharInfo::tone).collect::<Vec<_>>();
+        let spoken_tones = sandhi::spoken_tones(&res, sandhi);
+        for (char, tone) in res.iter_mut().zip(spoken_tones) {
+            char.pinyin.tone = tone;
         }
 
-        for (char, ans_char) in res.iter_mut().zip(answer) {
-            if char.tone() == ans_char.tone() {
-                answer_count.match_tone_char(char);
-                char.result.tone = State::Correct;
-            }
-        }
-        for char in &mut res {
-            if char.result.tone != State::Missing {
-                continue;
-            }
-            let tone_str = char.tone().to_string();
-            if answer_count.tones.contains_key(&tone_str) && answer_count.match_tone_char(char) {
-                char.result.tone = State::Misplaced;
+        options
+            .mode
+            .evaluator()
+            .evaluate(answer, &mut res, &mut answer_count, options, trace);
+        trace.finish(answer, &res);
+
+        if sandhi != Sandhi::Off {
+            for (char, tone) in res.iter_mut().zip(citation_tones) {
+                char.spoken_tone = Some(std::mem::replace(&mut char.pinyin.tone, tone));
             }
         }
-        
+
         Ok(Self {
             characters: res,
             verified: matches!(attempt.verified, Some(true)),
+            guesser: attempt.guesser.clone(),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::Mode;
+
+    fn attempt(word: &str, pinyin: &str) -> Attempt {
+        Attempt {
+            word: word.to_string(),
+            pinyin: pinyin.to_string(),
+            verified: None,
+            guesser: None,
+        }
+    }
+
+    #[test]
+    fn kana_splits_into_morae() {
+        let pinyin = Pinyin::parse("きょう", Scheme::Kana).unwrap();
+        assert_eq!(pinyin.initial.0.as_deref(), Some("きょ"));
+        assert_eq!(pinyin.vowel, "う");
+        assert_eq!(pinyin.tone, Tone(None));
+    }
+
+    #[test]
+    fn kana_tones_are_not_scored() {
+        let options = ScoringOptions {
+            scheme: Scheme::Kana,
+            ..Default::default()
+        };
+        let answer = attempt("一期一会", "ichi go ichi e")
+            .to_characters(4, Scheme::Kana)
+            .unwrap();
+
+        for mode in [Mode::Standard, Mode::Consuming] {
+            let options = ScoringOptions { mode, ..options };
+            let guess = attempt("一石二鳥", "itsu seki ni chou");
+            let res = CalculatedAttempt::from_attempt(&answer, &guess, &options).unwrap();
+            assert!(
+                res.characters
+                    .iter()
+                    .all(|char| char.result.tone == State::Missing)
+            );
+            assert_eq!(res.characters[0].result.whole, State::Correct);
+        }
+    }
+
+    #[test]
+    fn one_mora_readings_have_no_vowel() {
+        let options = ScoringOptions {
+            scheme: Scheme::Kana,
+            ..Default::default()
+        };
+        let answer = attempt("二", "ni").to_characters(1, Scheme::Kana).unwrap();
+        assert!(PinyinCount::from_attempt(&answer).vowels.is_empty());
+
+        let res = CalculatedAttempt::from_attempt(&answer, &attempt("五", "go"), &options).unwrap();
+        assert_eq!(res.characters[0].result.vowel, State::Missing);
+    }
+
+    #[test]
+    fn neutral_tone_counted_unless_legacy() {
+        let answer = attempt("东西", "dong1 xi0")
+            .to_characters(2, Scheme::Pinyin)
+            .unwrap();
+        let guess = attempt("吗哪", "ma0 na3");
+
+        let options = ScoringOptions::default();
+        let res = CalculatedAttempt::from_attempt(&answer, &guess, &options).unwrap();
+        assert_eq!(res.characters[0].result.tone, State::Misplaced);
+
+        let options = ScoringOptions {
+            legacy_neutral_tone: true,
+            ..options
+        };
+        let res = CalculatedAttempt::from_attempt(&answer, &guess, &options).unwrap();
+        assert_eq!(res.characters[0].result.tone, State::Missing);
+    }
+
+    #[test]
+    fn mandarin_tone_out_of_range() {
+        assert!(matches!(
+            Pinyin::parse("ma5", Scheme::Pinyin),
+            Err(PinyinError::InvalidTone(5, _))
+        ));
+        assert!(Pinyin::parse("maa5", Scheme::Jyutping).is_ok());
+    }
+}
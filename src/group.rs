@@ -0,0 +1,439 @@
+//! Custom answer lists uploaded by groups, stored as one file per group under
+//! `data/groups/`. The first uploader of a group becomes its admin, the only
+//! one allowed to change its lists afterwards.
+
+use crate::dict::{self, WordList, data_dir};
+use crate::error::{EntryError, GroupError, OmniError};
+use crate::model::{Answer, Attempt};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+
+const GROUPS_DIR: &str = "groups";
+
+/// An answer list uploaded by a group. Guesses are still looked up in the
+/// `base` dictionary list, whose length every entry must match.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CustomList {
+    pub base: String,
+    /// Validated entries, see `build`.
+    pub answers: Vec<Answer>,
+}
+
+/// An entry as uploaded.
+#[derive(Debug, Deserialize)]
+pub struct CustomEntry {
+    pub word: String,
+    /// Taken from the `base` list if omitted.
+    pub pinyin: Option<String>,
+    /// Taken from the `base` list if omitted, else left empty.
+    pub explanation: Option<String>,
+}
+
+/// A custom list without its entries.
+#[derive(Debug, Serialize)]
+pub struct CustomListSummary {
+    pub name: String,
+    pub base: String,
+    pub count: usize,
+}
+
+type GroupLists = BTreeMap<String, CustomList>;
+
+/// A group's file.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct Group {
+    /// Id of the player allowed to change the lists, none until the first
+    /// upload.
+    admin: Option<String>,
+    lists: GroupLists,
+}
+
+impl Group {
+    /// Checks that `admin_id` may change the lists, making it the admin of a
+    /// group that has none.
+    fn authorize(&mut self, admin_id: &str) -> Result<(), GroupError> {
+        if admin_id.is_empty() {
+            return Err(GroupError::NotAdmin(admin_id.to_string()));
+        }
+
+        match &self.admin {
+            Some(admin) if admin != admin_id => Err(GroupError::NotAdmin(admin_id.to_string())),
+            Some(_) => Ok(()),
+            None => {
+                self.admin = Some(admin_id.to_string());
+                Ok(())
+            }
+        }
+    }
+}
+
+lazy_static! {
+    /// Groups read so far, as they are on disk.
+    static ref GROUPS: RwLock<HashMap<String, Group>> = RwLock::new(HashMap::new());
+    /// Held while a group is changed, so that concurrent changes are not lost.
+    static ref WRITE: Mutex<()> = Mutex::new(());
+}
+
+/// Whether `id` is safe to use as a file name: ASCII letters, digits, `-` or
+/// `_`.
+pub(crate) fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn group_path(group_id: &str) -> Result<PathBuf, GroupError> {
+    if !is_valid_id(group_id) {
+        return Err(GroupError::InvalidGroupId(group_id.to_string()));
+    }
+
+    Ok(data_dir().join(GROUPS_DIR).join(format!("{group_id}.json")))
+}
+
+/// Runs `f` on a group, reading it from disk on first use. A group without a
+/// file has no admin and no lists, and is not cached.
+fn read_group<T>(group_id: &str, f: impl FnOnce(&Group) -> T) -> Result<T, OmniError> {
+    let path = group_path(group_id)?;
+    if let Some(group) = GROUPS.read().unwrap().get(group_id) {
+        return Ok(f(group));
+    }
+
+    let group: Group = match fs::read_to_string(&path) {
+        Ok(data) => serde_json::from_str(&data)?,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(f(&Group::default())),
+        Err(e) => Err(e)?,
+    };
+    let res = f(&group);
+    // a change made meanwhile is newer than what was read
+    GROUPS
+        .write()
+        .unwrap()
+        .entry(group_id.to_string())
+        .or_insert(group);
+
+    Ok(res)
+}
+
+/// Runs `f` on a copy of the lists of a group on behalf of `admin_id`, and
+/// saves the result, first to disk and then to the cache. Nothing is saved if
+/// `admin_id` is not the group's admin or if `f` fails.
+fn update_group<T>(
+    group_id: &str,
+    admin_id: &str,
+    f: impl FnOnce(&mut GroupLists) -> Result<T, OmniError>,
+) -> Result<T, OmniError> {
+    let path = group_path(group_id)?;
+    let _write = WRITE.lock().unwrap();
+
+    let mut group = read_group(group_id, Group::clone)?;
+    group.authorize(admin_id)?;
+    let res = f(&mut group.lists)?;
+
+    fs::create_dir_all(data_dir().join(GROUPS_DIR))?;
+    dict::write_file(&path, &serde_json::to_string_pretty(&group)?)?;
+    GROUPS.write().unwrap().insert(group_id.to_string(), group);
+
+    Ok(res)
+}
+
+/// Validates uploaded entries through the `Attempt` parsing path, filling in
+/// missing pinyin and explanations, and any derivation, example and
+/// abbreviation, from the `base` list or the main dictionary.
+pub fn build(base: &str, entries: Vec<CustomEntry>) -> Result<CustomList, OmniError> {
+    let list = dict::get().list(Some(base))?;
+    let main = dict::get().list(None)?;
+
+    Ok(validate(base, list, main, entries)?)
+}
+
+fn validate(
+    base: &str,
+    list: &WordList,
+    main: &WordList,
+    entries: Vec<CustomEntry>,
+) -> Result<CustomList, GroupError> {
+    if entries.is_empty() {
+        return Err(GroupError::EmptyList);
+    }
+
+    let mut answers = Vec::new();
+    let mut errors = Vec::new();
+    let mut seen = HashMap::new();
+
+    for (index, entry) in entries.into_iter().enumerate() {
+        if let Some(first) = seen.insert(entry.word.clone(), index) {
+            errors.push(EntryError {
+                index,
+                error: GroupError::DuplicateEntry(entry.word.clone(), first).to_string(),
+                word: entry.word,
+            });
+            continue;
+        }
+
+        let known = list
+            .lookup(&entry.word)
+            .or_else(|| main.lookup(&entry.word));
+
+        let Some(pinyin) = entry.pinyin.or_else(|| known.map(|a| a.pinyin.clone())) else {
+            errors.push(EntryError {
+                index,
+                error: GroupError::MissingPinyin(entry.word.clone()).to_string(),
+                word: entry.word,
+            });
+            continue;
+        };
+        let answer = Answer {
+            explanation: entry
+                .explanation
+                .or_else(|| known.map(|a| a.explanation.clone()))
+                .unwrap_or_default(),
+            derivation: known.and_then(|a| a.derivation.clone()),
+            example: known.and_then(|a| a.example.clone()),
+            abbreviation: known.and_then(|a| a.abbreviation.clone()),
+            word: entry.word,
+            pinyin,
+        };
+
+        let length = list
+            .length
+            .unwrap_or_else(|| answer.word.chars().count());
+        match Attempt::from(&answer).to_characters(length, list.scheme) {
+            Ok(_) => answers.push(answer),
+            Err(err) => errors.push(EntryError {
+                index,
+                word: answer.word,
+                error: err.to_string(),
+            }),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(GroupError::InvalidEntries(errors));
+    }
+
+    Ok(CustomList {
+        base: base.to_string(),
+        answers,
+    })
+}
+
+/// Saves `list` as `name` in a group, replacing any list of that name.
+/// `admin_id` must be the group's admin, and becomes it for a new group.
+pub fn upload(
+    group_id: &str,
+    admin_id: &str,
+    name: &str,
+    list: CustomList,
+) -> Result<(), OmniError> {
+    if name.is_empty() {
+        Err(GroupError::EmptyName)?
+    }
+
+    update_group(group_id, admin_id, |lists| {
+        lists.insert(name.to_string(), list);
+        Ok(())
+    })
+}
+
+/// The lists of a group, by name.
+pub fn summaries(group_id: &str) -> Result<Vec<CustomListSummary>, OmniError> {
+    read_group(group_id, |group| {
+        group
+            .lists
+            .iter()
+            .map(|(name, list)| CustomListSummary {
+                name: name.clone(),
+                base: list.base.clone(),
+                count: list.answers.len(),
+            })
+            .collect()
+    })
+}
+
+/// Removes the list `name` from a group. `admin_id` must be the group's
+/// admin.
+pub fn delete(group_id: &str, admin_id: &str, name: &str) -> Result<(), OmniError> {
+    update_group(group_id, admin_id, |lists| match lists.remove(name) {
+        Some(_) => Ok(()),
+        None => Err(GroupError::UnknownList(name.to_string()))?,
+    })
+}
+
+/// The list `name` of a group, none if there is no such list.
+pub fn get(group_id: &str, name: &str) -> Result<Option<CustomList>, OmniError> {
+    read_group(group_id, |group| group.lists.get(name).cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Scheme;
+    use std::process;
+
+    fn entry(word: &str, pinyin: Option<&str>) -> CustomEntry {
+        CustomEntry {
+            word: word.to_string(),
+            pinyin: pinyin.map(str::to_string),
+            explanation: None,
+        }
+    }
+
+    fn base() -> WordList {
+        let answer = Answer {
+            word: "一心一意".to_string(),
+            pinyin: "yi1 xin1 yi1 yi4".to_string(),
+            explanation: "只有一个心眼儿，没有别的考虑。".to_string(),
+            derivation: None,
+            example: None,
+            abbreviation: Some("yxyy".to_string()),
+        };
+        WordList::new(Some(4), Scheme::Pinyin, vec![answer], Vec::new())
+    }
+
+    fn empty_list() -> CustomList {
+        CustomList {
+            base: "idiom".to_string(),
+            answers: Vec::new(),
+        }
+    }
+
+    /// Removes the group's file when dropped, and the directories above it up
+    /// to the test data directory if they are left empty.
+    struct TestGroup(String);
+
+    impl TestGroup {
+        fn new(name: &str) -> Self {
+            Self(format!("test-{}-{name}", process::id()))
+        }
+    }
+
+    impl Drop for TestGroup {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(group_path(&self.0).unwrap());
+            let _ = fs::remove_dir(data_dir().join(GROUPS_DIR));
+            let _ = fs::remove_dir(data_dir());
+        }
+    }
+
+    #[test]
+    fn entries_filled_in_from_base() {
+        let base = base();
+        let list = validate(
+            "idiom",
+            &base,
+            &base,
+            vec![
+                entry("一心一意", None),
+                entry("三心二意", Some("san1 xin1 er4 yi4")),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(list.answers[0].pinyin, "yi1 xin1 yi1 yi4");
+        assert_eq!(list.answers[0].abbreviation.as_deref(), Some("yxyy"));
+        assert_eq!(list.answers[1].explanation, "");
+    }
+
+    #[test]
+    fn invalid_entries_reported_together() {
+        let base = base();
+        let res = validate(
+            "idiom",
+            &base,
+            &base,
+            vec![
+                entry("一心一意", None),
+                entry("心猿意马", None),
+                entry("一心", Some("yi1 xin1")),
+                entry("三心二意", Some("san1 xin1 er4 yi7")),
+                entry("一心一意", Some("yi1 xin1 yi1 yi4")),
+            ],
+        );
+
+        let Err(GroupError::InvalidEntries(errors)) = res else {
+            panic!("expected invalid entries, got {res:?}");
+        };
+        let indices = errors.iter().map(|e| e.index).collect::<Vec<_>>();
+        assert_eq!(indices, [1, 2, 3, 4]);
+        assert!(matches!(
+            validate("idiom", &base, &base, Vec::new()),
+            Err(GroupError::EmptyList)
+        ));
+    }
+
+    #[test]
+    fn invalid_group_ids_rejected() {
+        for id in ["", "../etc", "a b", "组"] {
+            assert!(matches!(
+                summaries(id),
+                Err(OmniError::Group(GroupError::InvalidGroupId(_)))
+            ));
+        }
+    }
+
+    #[test]
+    fn upload_list_delete() {
+        let group = TestGroup::new("lists");
+        let list = validate("idiom", &base(), &base(), vec![entry("一心一意", None)]).unwrap();
+
+        assert!(summaries(&group.0).unwrap().is_empty());
+        assert!(!group_path(&group.0).unwrap().exists());
+
+        upload(&group.0, "admin", "mine", list.clone()).unwrap();
+        upload(&group.0, "admin", "other", list).unwrap();
+        assert!(matches!(
+            upload(&group.0, "admin", "", empty_list()),
+            Err(OmniError::Group(GroupError::EmptyName))
+        ));
+
+        let names = summaries(&group.0)
+            .unwrap()
+            .into_iter()
+            .map(|summary| (summary.name, summary.count))
+            .collect::<Vec<_>>();
+        assert_eq!(names, [("mine".to_string(), 1), ("other".to_string(), 1)]);
+        assert_eq!(get(&group.0, "mine").unwrap().unwrap().base, "idiom");
+
+        // the file on disk matches the cache
+        let data = fs::read_to_string(group_path(&group.0).unwrap()).unwrap();
+        let saved: Group = serde_json::from_str(&data).unwrap();
+        assert_eq!(saved.admin.as_deref(), Some("admin"));
+        assert_eq!(saved.lists.keys().collect::<Vec<_>>(), ["mine", "other"]);
+
+        delete(&group.0, "admin", "mine").unwrap();
+        assert!(get(&group.0, "mine").unwrap().is_none());
+        assert!(matches!(
+            delete(&group.0, "admin", "mine"),
+            Err(OmniError::Group(GroupError::UnknownList(_)))
+        ));
+        assert_eq!(summaries(&group.0).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn only_admin_changes_lists() {
+        let group = TestGroup::new("admin");
+        let list = validate("idiom", &base(), &base(), vec![entry("一心一意", None)]).unwrap();
+
+        assert!(matches!(
+            upload(&group.0, "", "mine", list.clone()),
+            Err(OmniError::Group(GroupError::NotAdmin(_)))
+        ));
+        upload(&group.0, "admin", "mine", list.clone()).unwrap();
+
+        assert!(matches!(
+            upload(&group.0, "player", "mine", list),
+            Err(OmniError::Group(GroupError::NotAdmin(_)))
+        ));
+        assert!(matches!(
+            delete(&group.0, "player", "mine"),
+            Err(OmniError::Group(GroupError::NotAdmin(_)))
+        ));
+        assert!(get(&group.0, "mine").unwrap().is_some());
+    }
+}
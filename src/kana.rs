@@ -0,0 +1,157 @@
+//! Kana readings for the Japanese (`Scheme::Kana`) lists. Readings may be
+//! written in hiragana, katakana or Hepburn-style romaji; all of them are
+//! normalized to hiragana before being split into morae.
+
+#[rustfmt::skip]
+const ROMAJI: &[(&str, &str)] = &[
+    ("kya", "きゃ"), ("kyu", "きゅ"), ("kyo", "きょ"),
+    ("gya", "ぎゃ"), ("gyu", "ぎゅ"), ("gyo", "ぎょ"),
+    ("sha", "しゃ"), ("shu", "しゅ"), ("sho", "しょ"), ("shi", "し"),
+    ("sya", "しゃ"), ("syu", "しゅ"), ("syo", "しょ"),
+    ("jya", "じゃ"), ("jyu", "じゅ"), ("jyo", "じょ"),
+    ("zya", "じゃ"), ("zyu", "じゅ"), ("zyo", "じょ"),
+    ("cha", "ちゃ"), ("chu", "ちゅ"), ("cho", "ちょ"), ("chi", "ち"),
+    ("tya", "ちゃ"), ("tyu", "ちゅ"), ("tyo", "ちょ"), ("tsu", "つ"),
+    ("nya", "にゃ"), ("nyu", "にゅ"), ("nyo", "にょ"),
+    ("hya", "ひゃ"), ("hyu", "ひゅ"), ("hyo", "ひょ"),
+    ("bya", "びゃ"), ("byu", "びゅ"), ("byo", "びょ"),
+    ("pya", "ぴゃ"), ("pyu", "ぴゅ"), ("pyo", "ぴょ"),
+    ("mya", "みゃ"), ("myu", "みゅ"), ("myo", "みょ"),
+    ("rya", "りゃ"), ("ryu", "りゅ"), ("ryo", "りょ"),
+    ("ja", "じゃ"), ("ju", "じゅ"), ("jo", "じょ"), ("ji", "じ"),
+    ("ka", "か"), ("ki", "き"), ("ku", "く"), ("ke", "け"), ("ko", "こ"),
+    ("ga", "が"), ("gi", "ぎ"), ("gu", "ぐ"), ("ge", "げ"), ("go", "ご"),
+    ("sa", "さ"), ("si", "し"), ("su", "す"), ("se", "せ"), ("so", "そ"),
+    ("za", "ざ"), ("zi", "じ"), ("zu", "ず"), ("ze", "ぜ"), ("zo", "ぞ"),
+    ("ta", "た"), ("ti", "ち"), ("tu", "つ"), ("te", "て"), ("to", "と"),
+    ("da", "だ"), ("di", "ぢ"), ("du", "づ"), ("de", "で"), ("do", "ど"),
+    ("na", "な"), ("ni", "に"), ("nu", "ぬ"), ("ne", "ね"), ("no", "の"),
+    ("ha", "は"), ("hi", "ひ"), ("fu", "ふ"), ("hu", "ふ"), ("he", "へ"), ("ho", "ほ"),
+    ("ba", "ば"), ("bi", "び"), ("bu", "ぶ"), ("be", "べ"), ("bo", "ぼ"),
+    ("pa", "ぱ"), ("pi", "ぴ"), ("pu", "ぷ"), ("pe", "ぺ"), ("po", "ぽ"),
+    ("ma", "ま"), ("mi", "み"), ("mu", "む"), ("me", "め"), ("mo", "も"),
+    ("ya", "や"), ("yu", "ゆ"), ("yo", "よ"),
+    ("ra", "ら"), ("ri", "り"), ("ru", "る"), ("re", "れ"), ("ro", "ろ"),
+    ("wa", "わ"), ("wo", "を"),
+    ("a", "あ"), ("i", "い"), ("u", "う"), ("e", "え"), ("o", "お"),
+];
+
+/// Small kana that form one mora together with the kana before them.
+const COMBINING: &str = "ゃゅょぁぃぅぇぉ";
+
+fn is_hiragana(c: char) -> bool {
+    ('\u{3041}'..='\u{3096}').contains(&c) || c == 'ー'
+}
+
+fn katakana_to_hiragana(c: char) -> char {
+    match c {
+        '\u{30A1}'..='\u{30F6}' => char::from_u32(c as u32 - 0x60).unwrap(),
+        c => c,
+    }
+}
+
+fn is_vowel_or_y(c: Option<char>) -> bool {
+    matches!(c, Some('a' | 'i' | 'u' | 'e' | 'o' | 'y'))
+}
+
+fn romaji_to_hiragana(romaji: &str) -> Option<String> {
+    // on'yomi long vowels are spelled out in kana: `tō` is `とう`
+    let romaji = romaji
+        .to_lowercase()
+        .replace('ā', "aa")
+        .replace('ī', "ii")
+        .replace('ū', "uu")
+        .replace('ē', "ei")
+        .replace('ō', "ou");
+    let chars = romaji.chars().collect::<Vec<_>>();
+
+    let mut res = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        if c == 'n' && !is_vowel_or_y(next) {
+            res.push('ん');
+            // `nn` and `n'` spell one ん, but the `n` of `nna` starts な
+            let after = chars.get(i + 2).copied();
+            i += match next {
+                Some('\'') => 2,
+                Some('n') if !is_vowel_or_y(after) => 2,
+                _ => 1,
+            };
+            continue;
+        }
+        if c.is_ascii_alphabetic() && !"aiueo".contains(c) && next == Some(c) {
+            res.push('っ');
+            i += 1;
+            continue;
+        }
+
+        let (romaji, kana) = ROMAJI.iter().find(|(romaji, _)| {
+            chars[i..]
+                .iter()
+                .take(romaji.len())
+                .copied()
+                .eq(romaji.chars())
+        })?;
+        res.push_str(kana);
+        i += romaji.len();
+    }
+
+    Some(res)
+}
+
+/// Normalizes a reading to hiragana, or `None` if it is neither kana nor romaji.
+pub fn to_hiragana(reading: &str) -> Option<String> {
+    let kana = reading.chars().map(katakana_to_hiragana).collect::<String>();
+
+    if !kana.is_empty() && kana.chars().all(is_hiragana) {
+        Some(kana)
+    } else {
+        romaji_to_hiragana(reading).filter(|kana| !kana.is_empty())
+    }
+}
+
+/// Splits hiragana into morae: `きょう` is `きょ` + `う`, `いっ` is `い` + `っ`.
+pub fn morae(kana: &str) -> Vec<String> {
+    let mut res: Vec<String> = Vec::new();
+
+    for c in kana.chars() {
+        match res.last_mut() {
+            Some(last) if COMBINING.contains(c) => last.push(c),
+            _ => res.push(c.to_string()),
+        }
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn romaji_syllabic_n() {
+        assert_eq!(to_hiragana("annai").as_deref(), Some("あんない"));
+        assert_eq!(to_hiragana("onna").as_deref(), Some("おんな"));
+        assert_eq!(to_hiragana("kin'en").as_deref(), Some("きんえん"));
+        assert_eq!(to_hiragana("shinbun").as_deref(), Some("しんぶん"));
+        assert_eq!(to_hiragana("sannpo").as_deref(), Some("さんぽ"));
+        assert_eq!(to_hiragana("hon").as_deref(), Some("ほん"));
+    }
+
+    #[test]
+    fn romaji_long_vowels_and_geminates() {
+        assert_eq!(to_hiragana("tō").as_deref(), Some("とう"));
+        assert_eq!(to_hiragana("kitte").as_deref(), Some("きって"));
+    }
+
+    #[test]
+    fn kana_normalized_and_split() {
+        assert_eq!(to_hiragana("キョウ").as_deref(), Some("きょう"));
+        assert_eq!(morae("きょう"), ["きょ", "う"]);
+        assert_eq!(morae("いっ"), ["い", "っ"]);
+        assert_eq!(to_hiragana("x"), None);
+    }
+}
@@ -0,0 +1,74 @@
+//! The Handle game engine: a Chinese idiom Wordle scored on characters,
+//! pinyin initials, vowels and tones.
+//!
+//! - [`model`] holds the data types, [`game`] parses readings
+//!   ([`model::Pinyin::parse`]) and scores guesses
+//!   ([`model::CalculatedAttempt::from_attempt`], [`game::evaluate`]).
+//! - [`evaluator`], [`fuzzy`] and [`sandhi`] are the pluggable scoring rules,
+//!   [`explain`] traces them and [`compact`] is an allocation-free scorer for
+//!   bulk workloads.
+//! - [`dict`] loads and validates the word lists under `data/`, [`group`]
+//!   manages per-group custom lists, [`race`] multiplayer races and [`coop`]
+//!   shared boards, and [`stats`] keeps per-player records.
+//! - [`render`] (feature `render`) draws boards with typst and [`route`]
+//!   (feature `server`, on by default) is the axum HTTP API, with [`live`]
+//!   game updates over WebSocket.
+//!
+//! ```
+//! use handle::model::{Attempt, CalculatedAttempt, Scheme, ScoringOptions, State};
+//!
+//! let answer = Attempt {
+//!     word: "一心一意".into(),
+//!     pinyin: "yi1 xin1 yi1 yi4".into(),
+//!     verified: None,
+//!     guesser: None,
+//! };
+//! let guess = Attempt {
+//!     word: "一丝不苟".into(),
+//!     pinyin: "yi1 si1 bu4 gou3".into(),
+//!     verified: None,
+//!     guesser: None,
+//! };
+//!
+//! let answer = answer.to_characters(4, Scheme::Pinyin).unwrap();
+//! let scored =
+//!     CalculatedAttempt::from_attempt(&answer, &guess, &ScoringOptions::default()).unwrap();
+//! assert_eq!(scored.characters[0].result.whole, State::Correct);
+//! ```
+
+// the server's game state (`coop`, `group`, `live`, `race` and `stats`) is
+// not part of the documented API
+#![warn(missing_docs)]
+
+pub mod cedict;
+pub mod compact;
+pub mod config;
+pub mod constant;
+#[allow(missing_docs)]
+pub mod coop;
+pub mod dict;
+pub mod error;
+pub mod evaluator;
+pub mod explain;
+pub mod fuzzy;
+pub mod game;
+#[allow(missing_docs)]
+pub mod group;
+pub mod kana;
+#[cfg(feature = "server")]
+#[allow(missing_docs)]
+pub mod live;
+pub mod model;
+#[allow(missing_docs)]
+pub mod race;
+#[cfg(feature = "render")]
+pub mod render;
+#[cfg(feature = "server")]
+pub mod route;
+pub mod sandhi;
+#[allow(missing_docs)]
+pub mod stats;
+#[cfg(feature = "render")]
+mod util;
+pub mod variant;
+pub mod zhuyin;
@@ -0,0 +1,203 @@
+//! Live game updates. Clients subscribe to a game ID over a WebSocket and
+//! receive every `Event` published for it as a JSON text message. Requires the
+//! `server` feature.
+
+use crate::model::{Attempt, CalculatedAttempt, Guesser};
+use axum::extract::ws::{Message, WebSocket};
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::broadcast::{self, Receiver, Sender, error::RecvError};
+
+/// Events a slow subscriber may fall behind by before it starts missing some.
+const CHANNEL_CAPACITY: usize = 64;
+
+lazy_static! {
+    /// Channels of the games someone follows, dropped with their last
+    /// subscriber or when the game ends.
+    static ref CHANNELS: Mutex<HashMap<String, Channel>> = Mutex::new(HashMap::new());
+}
+
+struct Channel {
+    sender: Sender<String>,
+    /// Rows published so far. Boards are resent whole, so a row is only
+    /// published once the board has grown past it.
+    attempts: usize,
+}
+
+/// Something that happened in a game, sent with its kind in `type`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Event<'a> {
+    /// A new guess, `index` being its 0-based row on the board.
+    Attempt {
+        index: usize,
+        attempt: &'a CalculatedAttempt,
+    },
+    /// A hint from whoever runs the game.
+    Hint { hint: &'a str },
+    Finish {
+        won: bool,
+        attempts: usize,
+        answer: &'a Attempt,
+        /// Who found the answer, on a shared board.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        solver: Option<&'a Guesser>,
+    },
+}
+
+/// Follows `game_id`, receiving its events from now on.
+pub fn subscribe(game_id: &str) -> Receiver<String> {
+    let mut channels = CHANNELS.lock().unwrap();
+
+    channels
+        .entry(game_id.to_string())
+        .or_insert_with(|| Channel {
+            sender: broadcast::channel(CHANNEL_CAPACITY).0,
+            attempts: 0,
+        })
+        .sender
+        .subscribe()
+}
+
+/// Drops the channel of `game_id` if nobody is subscribed to it anymore.
+fn unsubscribe(game_id: &str) {
+    let mut channels = CHANNELS.lock().unwrap();
+
+    if channels
+        .get(game_id)
+        .is_some_and(|channel| channel.sender.receiver_count() == 0)
+    {
+        channels.remove(game_id);
+    }
+}
+
+/// Sends `event` to everyone subscribed to `game_id` and returns how many
+/// subscribers there were. An attempt already published is not sent again,
+/// and the channel is closed once the game finishes.
+pub fn publish(game_id: &str, event: &Event) -> Result<usize, serde_json::Error> {
+    let message = serde_json::to_string(event)?;
+    let mut channels = CHANNELS.lock().unwrap();
+
+    let Some(channel) = channels.get_mut(game_id) else {
+        return Ok(0);
+    };
+    if let Event::Attempt { index, .. } = event {
+        if *index < channel.attempts {
+            return Ok(0);
+        }
+        channel.attempts = index + 1;
+    }
+
+    let count = channel.sender.send(message).unwrap_or(0);
+    // every subscriber has disconnected, or none is left to wait for
+    if count == 0 || matches!(event, Event::Finish { .. }) {
+        channels.remove(game_id);
+    }
+
+    Ok(count)
+}
+
+/// Forwards the published events of `game_id` to `socket` until either side
+/// goes away.
+pub async fn forward(mut socket: WebSocket, game_id: String, mut events: Receiver<String>) {
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    if socket.send(Message::Text(event.into())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                None | Some(Err(_)) | Some(Ok(Message::Close(_))) => break,
+                Some(Ok(_)) => continue,
+            },
+        }
+    }
+
+    drop(events);
+    unsubscribe(&game_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+    use tokio::sync::broadcast::error::TryRecvError;
+
+    fn game_id(name: &str) -> String {
+        format!("test-{}-{name}", process::id())
+    }
+
+    fn attempt() -> CalculatedAttempt {
+        CalculatedAttempt {
+            characters: Vec::new(),
+            verified: true,
+            guesser: None,
+        }
+    }
+
+    #[test]
+    fn resent_attempts_published_once() {
+        let game_id = game_id("resent");
+        let mut events = subscribe(&game_id);
+        let attempt = attempt();
+
+        for index in [0, 0, 1, 1] {
+            publish(
+                &game_id,
+                &Event::Attempt {
+                    index,
+                    attempt: &attempt,
+                },
+            )
+            .unwrap();
+        }
+
+        assert!(events.try_recv().unwrap().contains(r#""index":0"#));
+        assert!(events.try_recv().unwrap().contains(r#""index":1"#));
+        assert_eq!(events.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn channel_closed_when_game_finishes() {
+        let game_id = game_id("finished");
+        let mut events = subscribe(&game_id);
+        let answer = Attempt {
+            word: "一心一意".to_string(),
+            pinyin: "yi1 xin1 yi1 yi4".to_string(),
+            verified: None,
+            guesser: None,
+        };
+        let finish = Event::Finish {
+            won: false,
+            attempts: 10,
+            answer: &answer,
+            solver: None,
+        };
+
+        assert_eq!(publish(&game_id, &finish).unwrap(), 1);
+        assert!(!CHANNELS.lock().unwrap().contains_key(&game_id));
+        assert!(events.try_recv().is_ok());
+        assert_eq!(events.try_recv(), Err(TryRecvError::Closed));
+    }
+
+    #[test]
+    fn channel_dropped_with_last_subscriber() {
+        let game_id = game_id("dropped");
+        let (first, second) = (subscribe(&game_id), subscribe(&game_id));
+
+        drop(first);
+        unsubscribe(&game_id);
+        assert!(CHANNELS.lock().unwrap().contains_key(&game_id));
+
+        drop(second);
+        unsubscribe(&game_id);
+        assert!(!CHANNELS.lock().unwrap().contains_key(&game_id));
+    }
+}
@@ -1,27 +1,51 @@
-mod constant;
-mod error;
-mod game;
-mod model;
-mod route;
-mod dict;
-mod util;
+mod cli;
+mod play;
 
-use axum::{
-    routing::{get, post},
-    Router,
-};
+use handle::config::{Config, DictMode};
+use handle::dict::{self, Dictionary, ValidationReport};
+use handle::route;
 
 #[tokio::main]
 async fn main() {
-    let app = Router::new()
-        .route("/ping", get(route::ping))
-        .route("/try_get_pinyin", get(route::try_get_pinyin))
-        .route("/start", post(route::start))
-        .route("/attempt", post(route::attempt));
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let command = match args.split_first().map(|(cmd, rest)| (cmd.as_str(), rest)) {
+        Some(("dict", rest)) => Some(cli::dict(rest)),
+        Some(("play", rest)) => Some(play::play(rest)),
+        _ => None,
+    };
+    match command {
+        Some(Ok(true)) => return,
+        Some(Ok(false)) => std::process::exit(1),
+        Some(Err(err)) => {
+            eprintln!("error: {err}");
+            std::process::exit(2);
+        }
+        None => {}
+    }
+
+    let config = Config::from_env();
+
+    let mut report = ValidationReport::default();
+    let dictionary = Dictionary::load(config.dict_mode, &mut report);
+    if !report.is_ok() {
+        eprint!("{report}");
+    }
+    match dictionary {
+        Ok(dictionary) => {
+            if !report.is_ok() && config.dict_mode == DictMode::Degraded {
+                eprintln!("starting in degraded mode");
+            }
+            dict::init(dictionary);
+        }
+        Err(err) => {
+            eprintln!("refusing to start: {err}");
+            std::process::exit(1);
+        }
+    }
 
     let listener = tokio::net::TcpListener::bind("127.0.0.1:55551")
         .await
         .unwrap();
 
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, route::router()).await.unwrap();
 }
@@ -1,102 +1,343 @@
+//! Data types shared by scoring, rendering and the HTTP API.
+
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 
-use crate::constant::IDIOM_LENGTH;
 use crate::error::PinyinError;
+use crate::evaluator::Mode;
+use crate::fuzzy::FuzzyTable;
+use crate::sandhi::{Sandhi, ToneDisplay};
+use crate::variant::Script;
+use crate::zhuyin::Zhuyin;
 
+/// A word list entry.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Answer {
+    /// The word, in simplified characters.
     pub word: String,
+    /// Its reading in the list's scheme, syllables separated by spaces.
     pub pinyin: String,
+    /// What the word means.
     pub explanation: String,
+    /// Where the word comes from, usually a quote from its source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derivation: Option<String>,
+    /// A sentence using the word, which `～` stands for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub example: Option<String>,
+    /// Initials of the pinyin syllables, e.g. `abdy` for 阿鼻地狱.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abbreviation: Option<String>,
 }
 
+/// A word with its reading, as guessed or as the answer of a game.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Attempt {
+    /// The word.
     pub word: String,
+    /// Its reading, syllables separated by spaces.
     pub pinyin: String,
+    /// Whether the word is in the dictionary, unknown if unset.
     pub verified: Option<bool>,
+    /// Who made the guess, on a board shared by a group.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guesser: Option<Guesser>,
 }
 
-#[derive(Debug, Deserialize)]
+/// A player on a shared board.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Guesser {
+    /// Id the player's stats and cooldown are kept under.
+    pub id: String,
+    /// Shown on the board instead of the id.
+    pub name: Option<String>,
+}
+
+/// A board sent by the client to be scored and rendered.
+#[derive(Clone, Debug, Deserialize)]
 pub struct Input {
+    /// The answer, as drawn by `/start`.
     pub answer: Attempt,
+    /// Every guess so far, oldest first.
     pub attempts: Vec<Attempt>,
+    /// Whether the game is over, worked out from the attempts if unset.
     pub finished: Option<bool>,
+    /// Word list the answer was drawn from, `dict::DEFAULT_LIST` if omitted.
+    pub list: Option<String>,
+    /// Group the game is played in, used to resolve its custom lists.
+    pub group_id: Option<String>,
+    /// Live game the newest attempt is published to, see `live`.
+    pub game_id: Option<String>,
+    /// Seconds a player must wait between guesses in the `game_id` game, see
+    /// `coop`.
+    pub cooldown: Option<u64>,
+    /// Player of a solo board, whose stats the game counts towards once
+    /// finished. A shared board credits the `guesser`s of its attempts
+    /// instead.
+    pub player: Option<Guesser>,
+    /// Fill in the `verified` flag of attempts that leave it unset by looking
+    /// them up in the word list.
+    #[serde(default)]
+    pub verify: bool,
+    /// Script to render the board in; guesses are scored in the form they are
+    /// listed under, see `dict::WordList::normalize`.
+    #[serde(default)]
+    pub script: Script,
+    /// Annotation above each character.
+    #[serde(default)]
+    pub annotation: Annotation,
+    /// How guesses are scored.
+    #[serde(flatten)]
+    pub rules: Rules,
+    /// Whether the board shows citation or spoken tones when `rules.sandhi` is
+    /// on.
+    #[serde(default)]
+    pub tone_display: ToneDisplay,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
-pub enum ToneExplicit {
+/// One guess scored against its own answer by `game::evaluate`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Pair {
+    /// The answer.
+    pub answer: Attempt,
+    /// The guess scored against it.
+    pub guess: Attempt,
+}
+
+/// Per-game scoring switches, see `ScoringOptions`.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Rules {
+    /// Score fuzzy-equivalent initials and vowels as `State::Near`.
+    pub fuzzy: bool,
+    /// See `ScoringOptions::legacy_neutral_tone`.
+    pub legacy_neutral_tone: bool,
+    /// Scoring rules, see `evaluator::Mode`.
+    pub mode: Mode,
+    /// Tone sandhi applied before tones are compared.
+    pub sandhi: Sandhi,
+}
+
+/// Phonetic annotation shown above each character on the board.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Annotation {
+    /// The reading as the list writes it.
+    #[default]
+    Pinyin,
+    /// The reading in Zhuyin, Mandarin only.
+    Zhuyin,
+}
+
+/// Phonetic scheme the readings of a word list are written in. Each splits a
+/// syllable into initial, vowel (final) and tone.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Scheme {
+    /// Mandarin Hanyu Pinyin, tones 1-4.
+    #[default]
+    Pinyin,
+    /// Cantonese Jyutping, tones 1-6.
+    Jyutping,
+    /// Japanese on'yomi in kana or romaji. The initial is the first mora, the
+    /// vowel the remaining morae, and there is no tone, so tones are never
+    /// scored.
+    Kana,
+}
+
+/// A Mandarin tone.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize)]
+pub enum MandarinTone {
+    /// Tone 1.
     High,
+    /// Tone 2.
     Rising,
+    /// Tone 3.
     Low,
+    /// Tone 4.
     Falling,
+    /// Written `0` or without a digit.
+    Neutral,
+}
+
+impl MandarinTone {
+    /// The digit the tone is written with, 0 for the neutral tone.
+    pub fn number(self) -> u8 {
+        match self {
+            MandarinTone::High => 1,
+            MandarinTone::Rising => 2,
+            MandarinTone::Low => 3,
+            MandarinTone::Falling => 4,
+            MandarinTone::Neutral => 0,
+        }
+    }
+}
+
+/// A Cantonese tone.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize)]
+pub enum CantoneseTone {
+    /// Tone 1.
+    HighLevel,
+    /// Tone 2.
+    HighRising,
+    /// Tone 3.
+    MidLevel,
+    /// Tone 4.
+    LowFalling,
+    /// Tone 5.
+    LowRising,
+    /// Tone 6.
+    LowLevel,
+}
+
+impl CantoneseTone {
+    /// The digit the tone is written with.
+    pub fn number(self) -> u8 {
+        match self {
+            CantoneseTone::HighLevel => 1,
+            CantoneseTone::HighRising => 2,
+            CantoneseTone::MidLevel => 3,
+            CantoneseTone::LowFalling => 4,
+            CantoneseTone::LowRising => 5,
+            CantoneseTone::LowLevel => 6,
+        }
+    }
+}
+
+/// A tone of either tonal scheme, serialized as the bare tone name (`High`,
+/// `LowLevel`).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ToneExplicit {
+    /// A Pinyin tone.
+    Mandarin(MandarinTone),
+    /// A Jyutping tone.
+    Cantonese(CantoneseTone),
+}
+
+impl ToneExplicit {
+    /// The digit the tone is written with.
+    pub fn number(self) -> u8 {
+        match self {
+            ToneExplicit::Mandarin(tone) => tone.number(),
+            ToneExplicit::Cantonese(tone) => tone.number(),
+        }
+    }
 }
 
 type Pronunciation = String;
 
+/// The initial of a syllable, none for a syllable that starts with its vowel.
 #[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct Initial(pub Option<String>);
 
 type Vowel = String;
 
+/// The tone of a syllable, none in a scheme or syllable without one.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
 pub struct Tone(pub Option<ToneExplicit>);
 
 impl Tone {
-    pub fn from_num(tone: Option<u8>) -> Result<Self, PinyinError> {
-        match tone {
-            None | Some(0) => Ok(Self(None)),
-            Some(1) => Ok(Self(Some(ToneExplicit::High))),
-            Some(2) => Ok(Self(Some(ToneExplicit::Rising))),
-            Some(3) => Ok(Self(Some(ToneExplicit::Low))),
-            Some(4) => Ok(Self(Some(ToneExplicit::Falling))),
-            Some(num) => Err(PinyinError::InvalidTone(num)),
+    /// The tone written with digit `tone` in `scheme`.
+    pub fn from_num(tone: Option<u8>, scheme: Scheme) -> Result<Self, PinyinError> {
+        match scheme {
+            Scheme::Pinyin => match tone {
+                None | Some(0) => Ok(MandarinTone::Neutral.into()),
+                Some(1) => Ok(MandarinTone::High.into()),
+                Some(2) => Ok(MandarinTone::Rising.into()),
+                Some(3) => Ok(MandarinTone::Low.into()),
+                Some(4) => Ok(MandarinTone::Falling.into()),
+                Some(num) => Err(PinyinError::InvalidTone(num, "0, 1, 2, 3, 4")),
+            },
+            // kana readings carry no tone, digits are already rejected by
+            // `kana::to_hiragana`
+            Scheme::Kana => Ok(Self(None)),
+            Scheme::Jyutping => match tone {
+                None => Ok(Self(None)),
+                Some(1) => Ok(CantoneseTone::HighLevel.into()),
+                Some(2) => Ok(CantoneseTone::HighRising.into()),
+                Some(3) => Ok(CantoneseTone::MidLevel.into()),
+                Some(4) => Ok(CantoneseTone::LowFalling.into()),
+                Some(5) => Ok(CantoneseTone::LowRising.into()),
+                Some(6) => Ok(CantoneseTone::LowLevel.into()),
+                Some(num) => Err(PinyinError::InvalidTone(num, "1, 2, 3, 4, 5, 6")),
+            },
+        }
+    }
+
+    /// The tone if it is a Mandarin one.
+    pub fn mandarin(self) -> Option<MandarinTone> {
+        match self.0 {
+            Some(ToneExplicit::Mandarin(tone)) => Some(tone),
+            _ => None,
         }
     }
 }
 
+impl From<MandarinTone> for Tone {
+    fn from(tone: MandarinTone) -> Self {
+        Self(Some(ToneExplicit::Mandarin(tone)))
+    }
+}
+
+impl From<CantoneseTone> for Tone {
+    fn from(tone: CantoneseTone) -> Self {
+        Self(Some(ToneExplicit::Cantonese(tone)))
+    }
+}
+
+/// The tone digit as shown to players. The neutral tone is shown without one,
+/// like a syllable without a tone.
 impl Display for Tone {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self.0 {
-                Some(ToneExplicit::High) => "1",
-                Some(ToneExplicit::Rising) => "2",
-                Some(ToneExplicit::Low) => "3",
-                Some(ToneExplicit::Falling) => "4",
-                None => "",
-            }
-        )
+        match self.0 {
+            Some(ToneExplicit::Mandarin(MandarinTone::Neutral)) | None => Ok(()),
+            Some(tone) => write!(f, "{}", tone.number()),
+        }
     }
 }
 
+/// A syllable's reading. Despite the name it holds Jyutping just as well, see
+/// `Scheme`.
 #[derive(Clone, Debug, Serialize)]
 pub struct Pinyin {
+    /// The syllable without its tone.
     pub pronunciation: Pronunciation,
+    /// The leading consonant, if any.
     pub initial: Initial,
+    /// The rest of the syllable.
     pub vowel: Vowel,
+    /// The tone, if the scheme writes one.
     pub tone: Tone,
 }
 
+/// The parts of a character that are scored.
 pub trait CharInfo {
+    /// The character itself.
     fn literal(&self) -> String;
 
+    /// Its syllable without the tone.
     fn pronunciation(&self) -> Pronunciation;
 
+    /// Its initial.
     fn initial(&self) -> Initial;
 
-    fn vowel(&self) -> Vowel;
+    /// Its vowel, if it has one: syllabic nasals (`m2`) and one-mora kana
+    /// readings have none.
+    fn vowel(&self) -> Option<Vowel>;
 
+    /// Its tone.
     fn tone(&self) -> Tone;
 }
 
-#[derive(Debug, Serialize)]
+/// A character with its reading.
+#[derive(Clone, Debug, Serialize)]
 pub struct Character {
+    /// The character.
     pub literal: String,
+    /// Its reading.
     pub pinyin: Pinyin,
 }
 
@@ -113,8 +354,8 @@ impl CharInfo for Character {
         self.pinyin.initial.to_owned()
     }
 
-    fn vowel(&self) -> Vowel {
-        self.pinyin.vowel.to_owned()
+    fn vowel(&self) -> Option<Vowel> {
+        Some(self.pinyin.vowel.to_owned()).filter(|vowel| !vowel.is_empty())
     }
 
     fn tone(&self) -> Tone {
@@ -122,12 +363,20 @@ impl CharInfo for Character {
     }
 }
 
-#[derive(Debug, Default, PartialEq, Serialize)]
+/// The color of a cell.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
 pub enum State {
+    /// Same as the answer's at the same position.
     Correct,
 
+    /// In the answer, at another position.
     Misplaced,
 
+    /// Fuzzy-equivalent to the answer's initial or vowel at the same position,
+    /// only with `ScoringOptions::fuzzy`.
+    Near,
+
+    /// Not in the answer, or its counts are used up.
     #[default]
     Missing,
 }
@@ -140,22 +389,49 @@ impl Display for State {
             match self {
                 State::Correct => "correct",
                 State::Misplaced => "misplaced",
+                State::Near => "near",
                 State::Missing => "missing",
             }
         )
     }
 }
 
+/// Everything scoring depends on besides the answer and the guess.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ScoringOptions<'a> {
+    /// Scheme of the readings.
+    pub scheme: Scheme,
+    /// Table of near initials and vowels, none to turn fuzzy matching off.
+    pub fuzzy: Option<&'a FuzzyTable>,
+    /// Score neutral tones the old way: they are never counted, so only a
+    /// neutral tone in the same position is marked, never a misplaced one.
+    pub legacy_neutral_tone: bool,
+    /// Normalize tones to their spoken form before comparing them, Mandarin
+    /// only.
+    pub sandhi: Sandhi,
+    /// Feedback given.
+    pub mode: Mode,
+}
+
+/// How many of each value of the answer are left to credit. The `match_*`
+/// methods take one if there is one left.
 #[derive(Clone, Debug, Default)]
 pub struct PinyinCount {
+    /// By character.
     pub literals: HashMap<String, usize>,
+    /// By syllable without tone.
     pub pronunciations: HashMap<String, usize>,
+    /// By initial.
     pub initials: HashMap<String, usize>,
+    /// By vowel.
     pub vowels: HashMap<String, usize>,
-    pub tones: HashMap<String, usize>,
+    /// By tone.
+    pub tones: HashMap<ToneExplicit, usize>,
 }
 
 impl PinyinCount {
+    /// Takes one `initial`.
+    #[expect(clippy::collapsible_if)]
     pub fn match_initial(&mut self, initial: String) -> bool {
         if let Some(v) = self.initials.get_mut(&initial) {
             if *v > 0 {
@@ -167,6 +443,7 @@ impl PinyinCount {
         false
     }
 
+    /// Takes one of the initial of `character`, if it has one.
     pub fn match_initial_char(&mut self, character: &impl CharInfo) -> bool {
         if let Some(initial) = character.initial().0 {
             self.match_initial(initial)
@@ -175,6 +452,8 @@ impl PinyinCount {
         }
     }
 
+    /// Takes one `vowel`.
+    #[expect(clippy::collapsible_if)]
     pub fn match_vowel(&mut self, vowel: String) -> bool {
         if let Some(v) = self.vowels.get_mut(&vowel) {
             if *v > 0 {
@@ -186,12 +465,19 @@ impl PinyinCount {
         false
     }
 
+    /// Takes one of the vowel of `character`, if it has one.
     pub fn match_vowel_char(&mut self, character: &impl CharInfo) -> bool {
-        self.match_vowel(character.vowel())
+        if let Some(vowel) = character.vowel() {
+            self.match_vowel(vowel)
+        } else {
+            false
+        }
     }
 
-    pub fn match_tone(&mut self, tone: Tone) -> bool {
-        if let Some(v) = self.tones.get_mut(&tone.to_string()) {
+    /// Takes one `tone`.
+    #[expect(clippy::collapsible_if)]
+    pub fn match_tone(&mut self, tone: ToneExplicit) -> bool {
+        if let Some(v) = self.tones.get_mut(&tone) {
             if *v > 0 {
                 *v -= 1;
                 return true;
@@ -201,15 +487,17 @@ impl PinyinCount {
         false
     }
 
+    /// Takes one of the tone of `character`, if it has one.
     pub fn match_tone_char(&mut self, character: &impl CharInfo) -> bool {
-        let tone = character.tone();
-        if tone.0.is_some() {
+        if let Some(tone) = character.tone().0 {
             self.match_tone(tone)
         } else {
             false
         }
     }
 
+    /// Takes one `pronunciation`.
+    #[expect(clippy::collapsible_if)]
     pub fn match_pronunciation(&mut self, pronunciation: Pronunciation) -> bool {
         if let Some(v) = self.pronunciations.get_mut(&pronunciation) {
             if *v > 0 {
@@ -221,10 +509,13 @@ impl PinyinCount {
         false
     }
 
+    /// Takes one of the pronunciation of `character`.
     pub fn match_pronunciation_char(&mut self, character: &impl CharInfo) -> bool {
         self.match_pronunciation(character.pronunciation())
     }
 
+    /// Takes one of `character` itself.
+    #[expect(clippy::collapsible_if)]
     pub fn match_whole_char(&mut self, character: &impl CharInfo) -> bool {
         if let Some(v) = self.literals.get_mut(&character.literal()) {
             if *v > 0 {
@@ -234,27 +525,38 @@ impl PinyinCount {
         }
 
         false
-
-        // self.match_pronunciation_char(character);
-        // self.match_initial_char(character);
-        // self.match_vowel_char(character);
-        // self.match_tone_char(character);
     }
 }
 
-#[derive(Debug, Default, Serialize)]
+/// The state of each part of a guessed character.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
 pub struct CharacterResult {
+    /// Of the character.
     pub whole: State,
+    /// Of its syllable without tone.
     pub pronunciation: State,
+    /// Of its initial.
     pub initial: State,
+    /// Of its vowel.
     pub vowel: State,
+    /// Of its tone.
     pub tone: State,
 }
 
+/// A guessed character, scored.
 #[derive(Debug, Serialize)]
 pub struct CalculatedCharacter {
+    /// The character, in the script of the board once displayed.
     pub literal: String,
+    /// Its reading.
     pub pinyin: Pinyin,
+    /// Only set when the board is annotated with Zhuyin.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zhuyin: Option<Zhuyin>,
+    /// The tone the syllable was scored with, only set with sandhi scoring.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spoken_tone: Option<Tone>,
+    /// How it scored.
     pub result: CharacterResult,
 }
 
@@ -271,8 +573,8 @@ impl CharInfo for CalculatedCharacter {
         self.pinyin.initial.to_owned()
     }
 
-    fn vowel(&self) -> Vowel {
-        self.pinyin.vowel.to_owned()
+    fn vowel(&self) -> Option<Vowel> {
+        Some(self.pinyin.vowel.to_owned()).filter(|vowel| !vowel.is_empty())
     }
 
     fn tone(&self) -> Tone {
@@ -280,16 +582,76 @@ impl CharInfo for CalculatedCharacter {
     }
 }
 
+/// A guess, scored.
 #[derive(Debug, Serialize)]
 // pub struct CalculatedAttempt(pub [CalculatedCharacter; IDIOM_LENGTH]);
 pub struct CalculatedAttempt {
-    pub(crate) characters: [CalculatedCharacter; IDIOM_LENGTH],
-    pub(crate) verified: bool,
+    /// One per character.
+    pub characters: Vec<CalculatedCharacter>,
+    /// Whether the word was found in the dictionary.
+    pub verified: bool,
+    /// Who made the guess, on a shared board.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guesser: Option<Guesser>,
 }
 
+/// A board as rendered.
 #[derive(Debug, Serialize)]
 pub struct Output {
+    /// Every guess, scored.
     pub result: Vec<CalculatedAttempt>,
+    /// Columns of the board.
+    pub word_length: usize,
+    /// Rows of the board.
     pub max_attempt_count: usize,
+    /// Whether the game is over.
     pub finished: bool,
+    /// Who found the answer, on a shared board.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub solver: Option<Guesser>,
+    /// The answer scored against itself, shown under a lost board.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub answer_row: Option<CalculatedAttempt>,
+    /// Shown once the game is over.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reveal: Option<Answer>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tones_serialize_by_name() {
+        let tone = Tone::from_num(Some(1), Scheme::Pinyin).unwrap();
+        assert_eq!(serde_json::to_string(&tone).unwrap(), r#""High""#);
+        let tone = Tone::from_num(Some(6), Scheme::Jyutping).unwrap();
+        assert_eq!(serde_json::to_string(&tone).unwrap(), r#""LowLevel""#);
+        assert_eq!(serde_json::to_string(&Tone(None)).unwrap(), "null");
+    }
+
+    #[test]
+    fn neutral_tone_shown_without_digit() {
+        let neutral = Tone::from_num(Some(0), Scheme::Pinyin).unwrap();
+        assert_eq!(neutral.to_string(), "");
+        assert_eq!(serde_json::to_string(&neutral).unwrap(), r#""Neutral""#);
+        let high = Tone::from_num(Some(1), Scheme::Pinyin).unwrap();
+        assert_eq!(high.to_string(), "1");
+    }
+
+    #[test]
+    fn tones_per_scheme() {
+        assert!(Tone::from_num(Some(6), Scheme::Pinyin).is_err());
+        assert!(Tone::from_num(Some(0), Scheme::Jyutping).is_err());
+        assert_eq!(
+            Tone::from_num(Some(4), Scheme::Pinyin).unwrap().mandarin(),
+            Some(MandarinTone::Falling)
+        );
+        assert_eq!(
+            Tone::from_num(Some(4), Scheme::Jyutping)
+                .unwrap()
+                .mandarin(),
+            None
+        );
+    }
 }
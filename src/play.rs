@@ -0,0 +1,218 @@
+use crate::cli;
+use handle::constant::MAX_ATTEMPT_COUNT;
+use handle::error::OmniError;
+use handle::model::{
+    Answer, Attempt, CalculatedAttempt, CalculatedCharacter, CharInfo, ScoringOptions, State,
+};
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::{RngExt, SeedableRng};
+use std::io::{self, BufRead, Write};
+
+const USAGE: &str = "\
+usage: handle play [--list NAME] [--seed N] [--attempts N] [--hard]
+
+Type a word to guess it, optionally followed by its pinyin (`一心一意 yi1 xin1 yi1 yi4`)
+if it is not in the dictionary. `--hard` requires every revealed character to be reused.";
+
+const RESET: &str = "\x1b[0m";
+
+fn usage(message: impl ToString) -> OmniError {
+    OmniError::Usage(format!("{}\n\n{USAGE}", message.to_string()))
+}
+
+/// Same colors as `image/main.typ`.
+fn rgb(state: State) -> (u8, u8, u8) {
+    match state {
+        State::Correct => (29, 156, 156),
+        State::Misplaced => (222, 117, 37),
+        State::Near => (201, 162, 39),
+        State::Missing => (181, 184, 190),
+    }
+}
+
+fn color(state: State) -> String {
+    let (r, g, b) = rgb(state);
+    format!("\x1b[38;2;{r};{g};{b}m")
+}
+
+fn underline(state: State) -> String {
+    let (r, g, b) = rgb(state);
+    format!("\x1b[4m\x1b[58;2;{r};{g};{b}m")
+}
+
+fn print_attempt(attempt: &CalculatedAttempt) {
+    let syllable = |character: &CalculatedCharacter| {
+        let result = &character.result;
+        let underline = match result.pronunciation {
+            State::Missing => String::new(),
+            state => underline(state),
+        };
+        format!(
+            "{underline}{}{}{}{}{}{}{RESET}",
+            color(result.initial),
+            character.initial().0.unwrap_or_default(),
+            color(result.vowel),
+            character.vowel().unwrap_or_default(),
+            color(result.tone),
+            character.tone(),
+        )
+    };
+
+    let syllables = attempt.characters.iter().map(syllable).collect::<Vec<_>>();
+    let literals = attempt
+        .characters
+        .iter()
+        .map(|character| match character.result.whole {
+            State::Missing => character.literal.clone(),
+            state => format!("\x1b[1m{}{}{RESET}", color(state), character.literal),
+        })
+        .collect::<Vec<_>>();
+
+    println!("{}", syllables.join(" "));
+    println!("{}", literals.join(" "));
+}
+
+/// Hard mode: characters found in earlier guesses must be reused, correct ones
+/// in the same position.
+fn check_hard(guess: &str, previous: &[CalculatedAttempt]) -> Result<(), String> {
+    let guess = guess.chars().map(String::from).collect::<Vec<_>>();
+
+    for attempt in previous {
+        for (i, character) in attempt.characters.iter().enumerate() {
+            match character.result.whole {
+                State::Correct if guess.get(i) != Some(&character.literal) => {
+                    return Err(format!(
+                        "character {} must be `{}`",
+                        i + 1,
+                        character.literal
+                    ));
+                }
+                State::Misplaced if !guess.contains(&character.literal) => {
+                    return Err(format!("guess must contain `{}`", character.literal));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Entry point for `handle play ...`. Returns whether the answer was found.
+pub fn play(args: &[String]) -> Result<bool, OmniError> {
+    let mut list = None;
+    let mut seed = None;
+    let mut max_attempts = MAX_ATTEMPT_COUNT;
+    let mut hard = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--list" => {
+                list = Some(
+                    args.next()
+                        .ok_or_else(|| usage("`--list` expects a name"))?,
+                );
+            }
+            "--seed" => {
+                seed = Some(
+                    args.next()
+                        .and_then(|v| v.parse().ok())
+                        .ok_or_else(|| usage("`--seed` expects a number"))?,
+                );
+            }
+            "--attempts" => {
+                max_attempts = args
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(|| usage("`--attempts` expects a number"))?;
+            }
+            "--hard" => hard = true,
+            other => return Err(usage(format!("unexpected argument `{other}`"))),
+        }
+    }
+
+    let (dictionary, _) = cli::load();
+    let list = dictionary.list(list.map(String::as_str))?;
+
+    let seed = seed.unwrap_or_else(|| rand::rng().random());
+    let mut rng = StdRng::seed_from_u64(seed);
+    let Some(answer) = list.answers.choose(&mut rng) else {
+        eprintln!("the list has no answers");
+        return Ok(false);
+    };
+
+    let length = list.length.unwrap_or_else(|| answer.word.chars().count());
+    let answer_characters = Attempt::from(answer).to_characters(length, list.scheme)?;
+    let options = ScoringOptions {
+        scheme: list.scheme,
+        ..Default::default()
+    };
+
+    println!("seed {seed}: guess the {length}-character word in {max_attempts} attempts");
+
+    let mut attempts: Vec<CalculatedAttempt> = Vec::new();
+    let mut lines = io::stdin().lock().lines();
+    while attempts.len() < max_attempts {
+        print!("{}> ", attempts.len() + 1);
+        io::stdout().flush()?;
+
+        let Some(line) = lines.next().transpose()? else {
+            break;
+        };
+        let mut parts = line.trim().splitn(2, char::is_whitespace);
+        let Some(word) = parts.next().filter(|word| !word.is_empty()) else {
+            continue;
+        };
+        let pinyin = parts.next().map(str::trim);
+
+        let entry = list.lookup(word);
+        let Some(pinyin) = pinyin.or(entry.map(|entry| entry.pinyin.as_str())) else {
+            println!("`{word}` is not in the dictionary, type its pinyin after it");
+            continue;
+        };
+        if hard && let Err(message) = check_hard(word, &attempts) {
+            println!("hard mode: {message}");
+            continue;
+        }
+
+        let attempt = Attempt {
+            word: word.to_string(),
+            pinyin: pinyin.to_string(),
+            verified: Some(entry.is_some()),
+            guesser: None,
+        };
+        let attempt = match CalculatedAttempt::from_attempt(&answer_characters, &attempt, &options)
+        {
+            Ok(attempt) => attempt,
+            Err(err) => {
+                println!("{err}");
+                continue;
+            }
+        };
+
+        print_attempt(&attempt);
+        let found = attempt
+            .characters
+            .iter()
+            .all(|c| c.result.whole == State::Correct);
+        attempts.push(attempt);
+        if found {
+            println!("found in {} attempts", attempts.len());
+            print_answer(answer);
+            return Ok(true);
+        }
+    }
+
+    println!("out of attempts");
+    print_answer(answer);
+    Ok(false)
+}
+
+fn print_answer(answer: &Answer) {
+    println!(
+        "{} ({}): {}",
+        answer.word, answer.pinyin, answer.explanation
+    );
+}
@@ -0,0 +1,315 @@
+//! Race mode: everyone in a group guesses the same answer on a board of their
+//! own. Races live in memory only.
+//!
+//! The players are set when the race starts. A race ranked by time ends as
+//! soon as one of them finds the answer; one ranked by attempts once all of
+//! them are done. Either way it ends when its time limit runs out.
+
+use crate::constant::MAX_ATTEMPT_COUNT;
+use crate::error::{OmniError, RaceError};
+use crate::model::{Attempt, Input, State};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Races older than this are dropped when a new one starts.
+const RACE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Time limit of a race unless given.
+pub const DEFAULT_TIME_LIMIT: Duration = Duration::from_secs(10 * 60);
+
+lazy_static! {
+    static ref RACES: Mutex<HashMap<String, Race>> = Mutex::new(HashMap::new());
+}
+
+/// How players who found the answer are ranked; the other key breaks ties.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RankBy {
+    /// First to find the answer wins, ending the race.
+    #[default]
+    Time,
+    /// Fewest attempts wins, once everyone is done.
+    Attempts,
+}
+
+/// A player on the roster, with their own board.
+#[derive(Debug)]
+pub struct Player {
+    pub id: String,
+    pub name: String,
+    /// Guesses so far, oldest first.
+    pub attempts: Vec<Attempt>,
+    /// Whole-character state of every cell, for the standings.
+    pub rows: Vec<Vec<State>>,
+    /// Time from the start of the race to the guess that found the answer.
+    pub solved_in: Option<Duration>,
+    /// Whether the game has been handed out by `Race::take_over`.
+    over: bool,
+}
+
+impl Player {
+    /// Whether the player found the answer or ran out of attempts.
+    pub fn is_done(&self) -> bool {
+        self.solved_in.is_some() || self.attempts.len() >= MAX_ATTEMPT_COUNT
+    }
+}
+
+/// A race in progress, or finished but not yet dropped.
+#[derive(Debug)]
+pub struct Race {
+    /// Answer and settings shared by every board; its `attempts` stay empty.
+    pub input: Input,
+    pub rank_by: RankBy,
+    time_limit: Duration,
+    started: Instant,
+    /// In the order of the roster.
+    pub players: Vec<Player>,
+}
+
+/// One player's place in the standings.
+#[derive(Debug, Serialize)]
+pub struct Standing {
+    /// 1-based, only for players who found the answer.
+    pub rank: Option<usize>,
+    pub id: String,
+    pub name: String,
+    pub attempts: usize,
+    pub time_ms: Option<u64>,
+    /// Whole-character state of every cell, hiding the guesses themselves.
+    pub rows: Vec<Vec<State>>,
+}
+
+/// Every player of a race, those who found the answer first, by rank.
+#[derive(Debug, Serialize)]
+pub struct Standings {
+    pub finished: bool,
+    /// Only revealed once the race is finished.
+    pub answer: Option<Attempt>,
+    pub max_attempt_count: usize,
+    pub players: Vec<Standing>,
+}
+
+impl Race {
+    /// A race between the players of `roster`, named after their ids until
+    /// they give a name.
+    pub fn new(input: Input, rank_by: RankBy, roster: &[String], time_limit: Duration) -> Self {
+        let mut players: Vec<Player> = Vec::new();
+        for id in roster {
+            if players.iter().all(|player| &player.id != id) {
+                players.push(Player {
+                    id: id.clone(),
+                    name: id.clone(),
+                    attempts: Vec::new(),
+                    rows: Vec::new(),
+                    solved_in: None,
+                    over: false,
+                });
+            }
+        }
+
+        Self {
+            input,
+            rank_by,
+            time_limit,
+            started: Instant::now(),
+            players,
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Whether the race is over, see the module documentation.
+    pub fn is_finished(&self) -> bool {
+        self.elapsed() >= self.time_limit
+            || self.players.iter().all(Player::is_done)
+            || (self.rank_by == RankBy::Time
+                && self.players.iter().any(|player| player.solved_in.is_some()))
+    }
+
+    pub fn player(&self, id: &str) -> Option<&Player> {
+        self.players.iter().find(|player| player.id == id)
+    }
+
+    /// The player with `id`, renamed to `name` if given.
+    pub fn player_mut(&mut self, id: &str, name: Option<&str>) -> Option<&mut Player> {
+        let player = self.players.iter_mut().find(|player| player.id == id)?;
+        if let Some(name) = name {
+            player.name = name.to_string();
+        }
+        Some(player)
+    }
+
+    /// The players whose game has ended since the last call, each returned
+    /// once.
+    pub fn take_over(&mut self) -> Vec<&Player> {
+        let finished = self.is_finished();
+        let mut over = Vec::new();
+        for (i, player) in self.players.iter_mut().enumerate() {
+            if !player.over && (finished || player.is_done()) {
+                player.over = true;
+                over.push(i);
+            }
+        }
+
+        over.into_iter().map(|i| &self.players[i]).collect()
+    }
+
+    pub fn standings(&self) -> Standings {
+        let mut solved = self
+            .players
+            .iter()
+            .filter(|player| player.solved_in.is_some())
+            .collect::<Vec<_>>();
+        match self.rank_by {
+            RankBy::Time => solved.sort_by_key(|p| (p.solved_in, p.attempts.len())),
+            RankBy::Attempts => solved.sort_by_key(|p| (p.attempts.len(), p.solved_in)),
+        }
+        let unsolved = self
+            .players
+            .iter()
+            .filter(|player| player.solved_in.is_none());
+
+        let players = solved
+            .into_iter()
+            .enumerate()
+            .map(|(i, player)| (Some(i + 1), player))
+            .chain(unsolved.map(|player| (None, player)))
+            .map(|(rank, player)| Standing {
+                rank,
+                id: player.id.clone(),
+                name: player.name.clone(),
+                attempts: player.attempts.len(),
+                time_ms: player.solved_in.map(|time| time.as_millis() as u64),
+                rows: player.rows.clone(),
+            })
+            .collect();
+
+        let finished = self.is_finished();
+        Standings {
+            finished,
+            answer: finished.then(|| self.input.answer.clone()),
+            max_attempt_count: MAX_ATTEMPT_COUNT,
+            players,
+        }
+    }
+}
+
+/// Starts a race, replacing any earlier race with the same id.
+pub fn start(race_id: &str, race: Race) -> Result<(), RaceError> {
+    if race_id.is_empty() {
+        return Err(RaceError::EmptyId);
+    }
+    if race.players.is_empty() {
+        return Err(RaceError::NoPlayers);
+    }
+
+    let mut races = RACES.lock().unwrap();
+    races.retain(|_, race| race.elapsed() < RACE_TTL);
+    races.insert(race_id.to_string(), race);
+
+    Ok(())
+}
+
+/// Runs `f` on a race while holding the lock on all races, so `f` should be
+/// quick.
+pub fn with_race<T>(
+    race_id: &str,
+    f: impl FnOnce(&mut Race) -> Result<T, OmniError>,
+) -> Result<T, OmniError> {
+    let mut races = RACES.lock().unwrap();
+    let race = races
+        .get_mut(race_id)
+        .ok_or_else(|| RaceError::UnknownRace(race_id.to_string()))?;
+
+    f(race)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn race(rank_by: RankBy, roster: &[&str], time_limit: Duration) -> Race {
+        let input = serde_json::from_str(
+            r#"{
+                "answer": { "word": "一心一意", "pinyin": "yi1 xin1 yi1 yi4", "verified": null },
+                "attempts": [],
+                "finished": null,
+                "list": null,
+                "group_id": null,
+                "game_id": null,
+                "cooldown": null
+            }"#,
+        )
+        .unwrap();
+        let roster = roster.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        Race::new(input, rank_by, &roster, time_limit)
+    }
+
+    fn guess(race: &mut Race, id: &str, solved: bool) {
+        let player = race.player_mut(id, None).unwrap();
+        player.attempts.push(race_attempt());
+        if solved {
+            player.solved_in = Some(Duration::from_secs(1));
+        }
+    }
+
+    fn race_attempt() -> Attempt {
+        Attempt {
+            word: "三心二意".to_string(),
+            pinyin: "san1 xin1 er4 yi4".to_string(),
+            verified: None,
+            guesser: None,
+        }
+    }
+
+    #[test]
+    fn roster_fixes_the_players() {
+        let mut race = race(RankBy::Time, &["ann", "bob", "ann"], DEFAULT_TIME_LIMIT);
+
+        assert_eq!(race.players.len(), 2);
+        assert!(race.player_mut("eve", None).is_none());
+        assert_eq!(race.player_mut("bob", Some("Bob")).unwrap().name, "Bob");
+    }
+
+    #[test]
+    fn first_solve_ends_timed_race() {
+        let mut race = race(RankBy::Time, &["ann", "bob"], DEFAULT_TIME_LIMIT);
+        guess(&mut race, "ann", false);
+        assert!(!race.is_finished());
+
+        guess(&mut race, "bob", true);
+        assert!(race.is_finished());
+        let over = race
+            .take_over()
+            .iter()
+            .map(|p| p.id.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(over, ["ann", "bob"]);
+        assert!(race.take_over().is_empty());
+    }
+
+    #[test]
+    fn race_by_attempts_waits_for_everyone() {
+        let mut race = race(RankBy::Attempts, &["ann", "bob"], DEFAULT_TIME_LIMIT);
+        guess(&mut race, "bob", true);
+        assert!(!race.is_finished());
+        assert_eq!(race.take_over().len(), 1);
+
+        for _ in 0..MAX_ATTEMPT_COUNT {
+            guess(&mut race, "ann", false);
+        }
+        assert!(race.is_finished());
+        assert_eq!(race.take_over()[0].id, "ann");
+    }
+
+    #[test]
+    fn race_ends_at_time_limit() {
+        let race = race(RankBy::Attempts, &["ann"], Duration::ZERO);
+        assert!(race.is_finished());
+    }
+}
@@ -0,0 +1,59 @@
+//! Board images. Requires the `render` feature.
+
+use crate::constant::IMAGE_DIR;
+use crate::error::OmniError;
+use crate::model::Output;
+use crate::race::Standings;
+use crate::stats::{Leaderboard, PlayerStats};
+use base64::{Engine, engine::general_purpose};
+use serde::Serialize;
+
+/// Renders a board to PNG with `typst compile image/main.typ` and returns it
+/// base64-encoded. Needs `typst` on `PATH`.
+pub fn render(data: &Output) -> Result<String, OmniError> {
+    compile("image/main.typ", data)
+}
+
+/// Renders the standings of a race with `image/standings.typ`.
+pub fn render_standings(data: &Standings) -> Result<String, OmniError> {
+    compile("image/standings.typ", data)
+}
+
+/// Renders a player's record with `image/stats.typ`.
+pub fn render_stats(data: &PlayerStats) -> Result<String, OmniError> {
+    compile("image/stats.typ", data)
+}
+
+/// Renders a group leaderboard with `image/leaderboard.typ`.
+pub fn render_leaderboard(data: &Leaderboard) -> Result<String, OmniError> {
+    compile("image/leaderboard.typ", data)
+}
+
+fn compile(template: &str, data: &impl Serialize) -> Result<String, OmniError> {
+    let uuid = crate::util::gen_uuid();
+    let data_file = &format!("data-{uuid}.json");
+    let data_path = &format!("{IMAGE_DIR}{data_file}");
+    let image_path = &format!("{IMAGE_DIR}handle-{uuid}.png");
+
+    std::fs::write(data_path, serde_json::to_string(data)?)?;
+
+    std::process::Command::new("typst")
+        .args([
+            "compile",
+            template,
+            "--ppi",
+            "200",
+            "--input",
+            &format!("path={data_file}"),
+            image_path,
+        ])
+        .output()?;
+
+    let image_binary = std::fs::read(image_path)?;
+    let image_base64 = general_purpose::STANDARD.encode(image_binary);
+
+    std::fs::remove_file(image_path)?;
+    std::fs::remove_file(data_path)?;
+
+    Ok(image_base64)
+}
@@ -1,17 +1,37 @@
-use crate::constant::{IMAGE_DIR, MAX_ATTEMPT_COUNT};
-use crate::dict::{ANSWERS, DICT, REVERSE_ANSWERS};
-use crate::error::OmniError;
-use crate::model::{Answer, CalculatedAttempt, Input, Output};
+//! The HTTP API. Requires the `server` feature.
 
+use crate::constant::MAX_ATTEMPT_COUNT;
+use crate::coop;
+use crate::dict;
+use crate::dict::WordList;
+use crate::error::{CoopError, DictError, EntryError, GroupError, OmniError, RaceError};
+use crate::explain::{Explanation, Trace};
+use crate::game;
+use crate::group::{self, CustomEntry, CustomList, CustomListSummary};
+use crate::live::{self, Event};
+use crate::model::{
+    Annotation, Answer, Attempt, CalculatedAttempt, Guesser, Input, Output, Pair, Rules,
+};
+use crate::race::{self, Player, Race, RankBy, Standings};
+use crate::render;
+use crate::sandhi::ToneDisplay;
+use crate::stats::{self, GameRecord, Leaderboard, Metric, Period, PlayerStats};
+use crate::variant::Script;
+use crate::zhuyin;
+
+use axum::Router;
+use axum::extract::ws::WebSocketUpgrade;
 use axum::extract::{Json, Query};
 use axum::http::StatusCode;
-use base64::{Engine, engine::general_purpose};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
 use rand::prelude::IndexedRandom;
-use serde::Serialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, SystemTime};
 
 #[derive(Debug, Serialize)]
-pub struct ImageResponse {
+struct ImageResponse {
     message: String,
     image_base64: Option<String>,
 }
@@ -32,74 +52,402 @@ impl ImageResponse {
     }
 }
 
-pub async fn ping(Query(params): Query<HashMap<String, String>>) -> String {
+#[derive(Debug, Serialize)]
+struct DataResponse<T> {
+    message: String,
+    data: Option<T>,
+}
+
+impl<T> DataResponse<T> {
+    fn new(message: impl ToString) -> Self {
+        Self {
+            message: message.to_string(),
+            data: None,
+        }
+    }
+
+    fn with_data(message: impl ToString, data: T) -> Self {
+        Self {
+            message: message.to_string(),
+            data: Some(data),
+        }
+    }
+}
+
+/// All HTTP endpoints. `dict::init` must have been called first.
+pub fn router() -> Router {
+    Router::new()
+        .route("/ping", get(ping))
+        .route("/try_get_pinyin", get(try_get_pinyin))
+        .route("/start", post(start))
+        .route("/attempt", post(attempt))
+        .route("/give_up", post(give_up))
+        .route("/evaluate", post(evaluate))
+        .route("/debug/explain", post(explain))
+        .route("/game/subscribe", get(subscribe))
+        .route("/game/hint", post(hint))
+        .route("/race/start", post(race_start))
+        .route("/race/attempt", post(race_attempt))
+        .route("/race/board", get(race_board))
+        .route("/race/standings", get(race_standings))
+        .route("/race/standings/image", get(race_standings_image))
+        .route("/stats", get(player_stats))
+        .route("/stats/image", get(player_stats_image))
+        .route("/group/leaderboard", get(group_leaderboard))
+        .route("/group/leaderboard/image", get(group_leaderboard_image))
+        .route(
+            "/group/lists",
+            get(group_lists)
+                .post(upload_group_list)
+                .delete(delete_group_list),
+        )
+}
+
+async fn ping(Query(params): Query<HashMap<String, String>>) -> String {
     params
         .get("arg")
         .unwrap_or(&"ping".into())
         .replace("i", "o")
 }
 
-pub async fn start() -> Json<Answer> {
-    Json(ANSWERS.choose(&mut rand::rng()).unwrap().clone())
+/// A group's custom list shadows the dictionary list of the same name.
+fn custom_list(
+    group_id: Option<&String>,
+    list: Option<&String>,
+) -> Result<Option<CustomList>, OmniError> {
+    match (group_id, list) {
+        (Some(group_id), Some(list)) => group::get(group_id, list),
+        _ => Ok(None),
+    }
 }
 
-fn gen_image(data: Output) -> Result<String, OmniError> {
-    let uuid = crate::util::gen_uuid();
-    let data_file = &format!("data-{uuid}.json");
-    let data_path = &format!("{IMAGE_DIR}{data_file}");
-    let image_path = &format!("{IMAGE_DIR}handle-{uuid}.png");
+/// The dictionary list guesses are checked against: a custom list's base, or
+/// the named list itself.
+fn base_list(
+    group_id: Option<&String>,
+    list: Option<&String>,
+) -> Result<&'static WordList, OmniError> {
+    let base = custom_list(group_id, list)?
+        .map(|custom| custom.base)
+        .or(list.cloned());
 
-    std::fs::write(data_path, serde_json::to_string(&data)?)?;
+    Ok(dict::get().list(base.as_deref())?)
+}
 
-    std::process::Command::new("typst")
-        .args([
-            "compile",
-            "image/main.typ",
-            "--ppi",
-            "200",
-            "--input",
-            &format!("path={data_file}"),
-            image_path,
-        ])
-        .output()?;
+/// Reads the `script` query parameter, defaulting to simplified.
+fn script(params: &HashMap<String, String>) -> Script {
+    match params.get("script").map(String::as_str) {
+        Some("traditional") => Script::Traditional,
+        _ => Script::Simplified,
+    }
+}
 
-    let image_binary = std::fs::read(image_path)?;
-    let image_base64 = general_purpose::STANDARD.encode(image_binary);
+fn convert_answer(answer: Answer, script: Script) -> Answer {
+    let variants = &dict::get().variants;
 
-    std::fs::remove_file(image_path)?;
-    std::fs::remove_file(data_path)?;
+    let convert = |text: &Option<String>| text.as_ref().map(|text| variants.convert(text, script));
 
-    Ok(image_base64)
+    Answer {
+        word: variants.convert(&answer.word, script),
+        explanation: variants.convert(&answer.explanation, script),
+        derivation: convert(&answer.derivation),
+        example: convert(&answer.example),
+        ..answer
+    }
 }
 
-fn attempt_inner(input: Input) -> Result<String, OmniError> {
-    eprintln!("{:#?}", input);
+/// Draws a random answer from a group's custom list or a dictionary list.
+fn pick_answer(
+    group_id: Option<&String>,
+    list: Option<&String>,
+) -> Result<Option<Answer>, OmniError> {
+    let mut rng = rand::rng();
+
+    Ok(match custom_list(group_id, list)? {
+        Some(custom) => custom.answers.choose(&mut rng).cloned(),
+        None => dict::get()
+            .list(list.map(String::as_str))?
+            .answers
+            .choose(&mut rng)
+            .cloned(),
+    })
+}
+
+/// Draws an answer. With `game_id`, the game counts towards player stats once
+/// finished, see `stats`.
+async fn start(Query(params): Query<HashMap<String, String>>) -> Response {
+    let answer = pick_answer(params.get("group_id"), params.get("list"))
+        .and_then(|answer| Ok(answer.ok_or(DictError::NoAnswers)?));
+    let answer = match answer {
+        Ok(answer) => answer,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(DataResponse::<()>::new(format!("error: {}", e))),
+            )
+                .into_response();
+        }
+    };
+    if let Some(game_id) = params.get("game_id") {
+        stats::open(game_id);
+    }
+
+    Json(convert_answer(answer, script(&params))).into_response()
+}
+
+/// Resolves the input's word list and scores every attempt, recording a trace
+/// for each when `explain` is set.
+fn score(
+    input: &mut Input,
+    explain: bool,
+) -> Result<(usize, Vec<(CalculatedAttempt, Trace)>), OmniError> {
+    let list = base_list(input.group_id.as_ref(), input.list.as_ref())?;
+    let variants = &dict::get().variants;
+
+    input.answer.word = list.normalize(&input.answer.word, variants);
+    for attempt in &mut input.attempts {
+        attempt.word = list.normalize(&attempt.word, variants);
+        if input.verify {
+            attempt
+                .verified
+                .get_or_insert_with(|| list.lookup(&attempt.word).is_some());
+        }
+    }
+
+    let length = list
+        .length
+        .unwrap_or_else(|| input.answer.word.chars().count());
+    let answer = input.answer.to_characters(length, list.scheme)?;
+
+    let options = input.rules.options(list.scheme, &dict::get().fuzzy);
+    let scored = input
+        .attempts
+        .iter()
+        .map(|attempt| {
+            if explain {
+                let mut trace = Trace::default();
+                CalculatedAttempt::traced(&answer, attempt, &options, &mut trace)
+                    .map(|attempt| (attempt, trace))
+            } else {
+                CalculatedAttempt::from_attempt(&answer, attempt, &options)
+                    .map(|attempt| (attempt, Trace::default()))
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((length, scored))
+}
 
-    let finished = input.finished.unwrap_or_else(|| {
-        input.attempts.len() >= MAX_ATTEMPT_COUNT
-            || input
-                .attempts
-                .last()
-                .is_some_and(|last| last.word == input.answer.word)
+/// Publishes the newest attempt of a game, if any, and its end if it is over.
+fn publish_attempt(
+    game_id: &str,
+    input: &Input,
+    newest: Option<&CalculatedAttempt>,
+    finished: bool,
+    won: bool,
+    solver: Option<&Guesser>,
+) -> Result<(), OmniError> {
+    if let Some(attempt) = newest {
+        let index = input.attempts.len() - 1;
+        live::publish(game_id, &Event::Attempt { index, attempt })?;
+    }
+    if finished {
+        live::publish(
+            game_id,
+            &Event::Finish {
+                won,
+                attempts: input.attempts.len(),
+                answer: &input.answer,
+                solver,
+            },
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Converts scored attempts to the script, tones and annotation the board is
+/// shown in.
+fn display(input: &Input, scored: Vec<(CalculatedAttempt, Trace)>) -> Vec<CalculatedAttempt> {
+    let variants = &dict::get().variants;
+
+    let mut result = scored
+        .into_iter()
+        .map(|(attempt, _)| attempt)
+        .collect::<Vec<_>>();
+    for character in result.iter_mut().flat_map(|attempt| &mut attempt.characters) {
+        character.literal = variants.convert(&character.literal, input.script);
+        if input.tone_display == ToneDisplay::Spoken
+            && let Some(tone) = character.spoken_tone
+        {
+            character.pinyin.tone = tone;
+        }
+        if input.annotation == Annotation::Zhuyin {
+            character.zhuyin = zhuyin::from_pinyin(&character.pinyin);
+        }
+    }
+
+    result
+}
+
+/// Records a finished game for its players. A solo board is recorded for its
+/// `player`. On a shared board, a won game is credited to the solver alone, as
+/// the others did not find the answer; a lost game counts as lost for everyone
+/// who guessed in it.
+fn record_players(input: &Input, won: bool, solver: Option<&Guesser>, started: SystemTime) {
+    let now = SystemTime::now();
+    let game = GameRecord {
+        group_id: input.group_id.clone(),
+        finished_at: stats::unix_time(now),
+        won,
+        attempts: input.attempts.len(),
+        duration_ms: won
+            .then(|| now.duration_since(started).unwrap_or_default().as_millis() as u64),
+    };
+
+    let guessers = input
+        .attempts
+        .iter()
+        .filter_map(|a| a.guesser.as_ref())
+        .collect::<Vec<_>>();
+    let players = match (&input.player, won) {
+        (Some(player), _) if guessers.is_empty() => vec![player],
+        (_, true) => solver.into_iter().collect(),
+        (_, false) => guessers,
+    };
+    let mut recorded = HashSet::new();
+    for guesser in players {
+        if recorded.insert(&guesser.id)
+            && let Err(e) = stats::record(&guesser.id, guesser.name.as_deref(), game.clone())
+        {
+            eprintln!("failed to record a game for `{}`: {e}", guesser.id);
+        }
+    }
+}
+
+/// The answer as shown once a game is `over`: the row under a lost board, and
+/// the card revealing the word with its explanation.
+fn reveal(
+    input: &Input,
+    over: bool,
+    won: bool,
+) -> Result<(Option<CalculatedAttempt>, Option<Answer>), OmniError> {
+    if !over {
+        return Ok((None, None));
+    }
+
+    let answer_row = if won {
+        None
+    } else {
+        let mut row = input.clone();
+        row.attempts = vec![input.answer.clone()];
+        let (_, scored) = score(&mut row, false)?;
+        display(&row, scored).pop()
+    };
+
+    let word = &input.answer.word;
+    let known = match custom_list(input.group_id.as_ref(), input.list.as_ref())? {
+        Some(custom) => custom.answers.into_iter().find(|a| &a.word == word),
+        None => None,
+    }
+    .or_else(|| {
+        let list = base_list(input.group_id.as_ref(), input.list.as_ref()).ok()?;
+        list.lookup(word).cloned()
     });
+    let answer = match known {
+        Some(known) => Answer {
+            word: word.clone(),
+            pinyin: input.answer.pinyin.clone(),
+            ..known
+        },
+        None => Answer {
+            word: word.clone(),
+            pinyin: input.answer.pinyin.clone(),
+            explanation: String::new(),
+            derivation: None,
+            example: None,
+            abbreviation: None,
+        },
+    };
+
+    Ok((answer_row, Some(convert_answer(answer, input.script))))
+}
 
-    let answer = input.answer.try_into()?;
+/// Scores and renders a board. `give_up` ends the game as lost without a new
+/// attempt.
+fn attempt_inner(mut input: Input, give_up: bool) -> Result<String, OmniError> {
+    eprintln!("{:#?}", input);
 
-    let output = Output {
-        result: input
+    let (length, scored) = score(&mut input, false)?;
+
+    // the guess held against the guesser's cooldown, if it is a new one; it
+    // is taken back unless the board is rendered
+    let mut reservation = None;
+    if let Some(cooldown) = input.cooldown
+        && !give_up
+    {
+        let game_id = input.game_id.as_ref().ok_or(CoopError::MissingGameId)?;
+        let guesser = input
             .attempts
-            .iter()
-            .map(|attempt| CalculatedAttempt::from_attempt(&answer, attempt))
-            .collect::<Result<Vec<_>, _>>()?,
+            .last()
+            .and_then(|last| last.guesser.as_ref())
+            .ok_or(CoopError::MissingGuesser)?;
+        let count = input.attempts.len();
+        reservation = coop::reserve(game_id, &guesser.id, count, Duration::from_secs(cooldown))?;
+    }
+
+    let won = input
+        .attempts
+        .last()
+        .filter(|last| !give_up && last.word == input.answer.word);
+    let finished = give_up
+        || input
+            .finished
+            .unwrap_or_else(|| input.attempts.len() >= MAX_ATTEMPT_COUNT || won.is_some());
+    let solver = won.and_then(|last| last.guesser.clone());
+    let won = won.is_some();
+
+    let (answer_row, reveal) = reveal(&input, finished, won)?;
+    let output = Output {
+        result: display(&input, scored),
+        word_length: length,
         max_attempt_count: MAX_ATTEMPT_COUNT,
         finished,
+        solver,
+        answer_row,
+        reveal,
     };
 
-    gen_image(output)
+    let image = render::render(&output)?;
+
+    // only a board that was rendered counts, for the cooldown, the stats and
+    // the spectators
+    if let Some(reservation) = reservation {
+        reservation.commit();
+    }
+    if let Some(game_id) = &input.game_id {
+        if !finished {
+            stats::open(game_id);
+        } else if let Some(started) = stats::close(game_id) {
+            record_players(&input, won, output.solver.as_ref(), started);
+        }
+
+        let newest = output.result.last().filter(|_| !give_up);
+        publish_attempt(
+            game_id,
+            &input,
+            newest,
+            finished,
+            won,
+            output.solver.as_ref(),
+        )?;
+    }
+
+    Ok(image)
 }
 
-pub async fn attempt(Json(input): Json<Input>) -> (StatusCode, Json<ImageResponse>) {
-    match attempt_inner(input) {
+async fn attempt(Json(input): Json<Input>) -> (StatusCode, Json<ImageResponse>) {
+    match attempt_inner(input, false) {
         Ok(image_base64) => (
             StatusCode::OK,
             Json(ImageResponse::with_image("ok", image_base64)),
@@ -111,11 +459,715 @@ pub async fn attempt(Json(input): Json<Input>) -> (StatusCode, Json<ImageRespons
     }
 }
 
-pub async fn try_get_pinyin(Query(params): Query<HashMap<String, String>>) -> Json<Option<Answer>> {
+/// Ends the game as lost and renders the board with the answer revealed.
+async fn give_up(Json(input): Json<Input>) -> (StatusCode, Json<ImageResponse>) {
+    match attempt_inner(input, true) {
+        Ok(image_base64) => (
+            StatusCode::OK,
+            Json(ImageResponse::with_image("ok", image_base64)),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ImageResponse::new(format!("error: {}", e))),
+        ),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct EvaluateInput {
+    list: Option<String>,
+    group_id: Option<String>,
+    /// See `Input::verify`.
+    #[serde(default)]
+    verify: bool,
+    #[serde(flatten)]
+    rules: Rules,
+    pairs: Vec<Pair>,
+}
+
+#[derive(Debug, Serialize)]
+struct EvaluateItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<CalculatedAttempt>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn evaluate_inner(mut input: EvaluateInput) -> Result<Vec<EvaluateItem>, OmniError> {
+    let list = base_list(input.group_id.as_ref(), input.list.as_ref())?;
+    let variants = &dict::get().variants;
+
+    for pair in &mut input.pairs {
+        pair.answer.word = list.normalize(&pair.answer.word, variants);
+        pair.guess.word = list.normalize(&pair.guess.word, variants);
+        if input.verify {
+            pair.guess
+                .verified
+                .get_or_insert_with(|| list.lookup(&pair.guess.word).is_some());
+        }
+    }
+
+    let options = input.rules.options(list.scheme, &dict::get().fuzzy);
+
+    Ok(game::evaluate(&input.pairs, list.length, &options)
+        .into_iter()
+        .map(|res| match res {
+            Ok(attempt) => EvaluateItem {
+                result: Some(attempt),
+                error: None,
+            },
+            Err(e) => EvaluateItem {
+                result: None,
+                error: Some(e.to_string()),
+            },
+        })
+        .collect())
+}
+
+/// Scores many answer/guess pairs at once without rendering them.
+async fn evaluate(
+    Json(input): Json<EvaluateInput>,
+) -> (StatusCode, Json<DataResponse<Vec<EvaluateItem>>>) {
+    match evaluate_inner(input) {
+        Ok(items) => (StatusCode::OK, Json(DataResponse::with_data("ok", items))),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(DataResponse::new(format!("error: {}", e))),
+        ),
+    }
+}
+
+/// Debug endpoint explaining how every cell of the board was scored, as JSON
+/// or, with `format=text`, as plain text.
+async fn explain(
+    Query(params): Query<HashMap<String, String>>,
+    Json(mut input): Json<Input>,
+) -> Response {
+    let explanations = score(&mut input, true).map(|(_, scored)| {
+        scored
+            .iter()
+            .map(|(attempt, trace)| Explanation::new(attempt, trace))
+            .collect::<Vec<_>>()
+    });
+
+    match (explanations, params.get("format").map(String::as_str)) {
+        (Ok(explanations), Some("text")) => {
+            let text: String = explanations.iter().map(ToString::to_string).collect();
+            (StatusCode::OK, text).into_response()
+        }
+        (Ok(explanations), _) => (
+            StatusCode::OK,
+            Json(DataResponse::with_data("ok", explanations)),
+        )
+            .into_response(),
+        (Err(e), _) => (
+            StatusCode::BAD_REQUEST,
+            Json(DataResponse::<()>::new(format!("error: {}", e))),
+        )
+            .into_response(),
+    }
+}
+
+/// WebSocket endpoint streaming the `live::Event`s of the `game_id` game.
+async fn subscribe(
+    ws: WebSocketUpgrade,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    let Some(game_id) = params.get("game_id") else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(DataResponse::<()>::new("error: missing `game_id`")),
+        )
+            .into_response();
+    };
+
+    let events = live::subscribe(game_id);
+    let game_id = game_id.clone();
+    ws.on_upgrade(move |socket| live::forward(socket, game_id, events))
+}
+
+#[derive(Debug, Deserialize)]
+struct HintInput {
+    game_id: String,
+    hint: String,
+}
+
+/// Sends a hint to everyone following a game, returning how many received it.
+async fn hint(Json(input): Json<HintInput>) -> (StatusCode, Json<DataResponse<usize>>) {
+    match live::publish(&input.game_id, &Event::Hint { hint: &input.hint }) {
+        Ok(count) => (StatusCode::OK, Json(DataResponse::with_data("ok", count))),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(DataResponse::new(format!("error: {}", e))),
+        ),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RaceStartInput {
+    race_id: String,
+    list: Option<String>,
+    group_id: Option<String>,
+    #[serde(default)]
+    script: Script,
+    #[serde(default)]
+    annotation: Annotation,
+    #[serde(flatten)]
+    rules: Rules,
+    #[serde(default)]
+    tone_display: ToneDisplay,
+    #[serde(default)]
+    rank_by: RankBy,
+    /// Ids of the players.
+    players: Vec<String>,
+    /// In seconds, `race::DEFAULT_TIME_LIMIT` if omitted.
+    time_limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct RaceInfo {
+    word_length: usize,
+    max_attempt_count: usize,
+    /// In seconds.
+    time_limit: u64,
+}
+
+fn race_start_inner(input: RaceStartInput) -> Result<RaceInfo, OmniError> {
+    let answer =
+        pick_answer(input.group_id.as_ref(), input.list.as_ref())?.ok_or(DictError::NoAnswers)?;
+    let list = base_list(input.group_id.as_ref(), input.list.as_ref())?;
+
+    let time_limit = input
+        .time_limit
+        .map_or(race::DEFAULT_TIME_LIMIT, Duration::from_secs);
+    let info = RaceInfo {
+        word_length: list.length.unwrap_or_else(|| answer.word.chars().count()),
+        max_attempt_count: MAX_ATTEMPT_COUNT,
+        time_limit: time_limit.as_secs(),
+    };
+    let board = Input {
+        answer: Attempt::from(&answer),
+        attempts: Vec::new(),
+        finished: None,
+        list: input.list,
+        group_id: input.group_id,
+        game_id: None,
+        cooldown: None,
+        player: None,
+        verify: false,
+        script: input.script,
+        annotation: input.annotation,
+        rules: input.rules,
+        tone_display: input.tone_display,
+    };
+    let race = Race::new(board, input.rank_by, &input.players, time_limit);
+    race::start(&input.race_id, race)?;
+
+    Ok(info)
+}
+
+/// Starts a race on a random answer, which stays hidden until the race is over.
+async fn race_start(
+    Json(input): Json<RaceStartInput>,
+) -> (StatusCode, Json<DataResponse<RaceInfo>>) {
+    match race_start_inner(input) {
+        Ok(info) => (StatusCode::OK, Json(DataResponse::with_data("ok", info))),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(DataResponse::new(format!("error: {}", e))),
+        ),
+    }
+}
+
+/// A finished race game: player id, name and record.
+type RaceGame = (String, Option<String>, GameRecord);
+
+#[derive(Debug, Deserialize)]
+struct RaceAttemptInput {
+    race_id: String,
+    player_id: String,
+    /// Shown in the standings, the player id if never given.
+    name: Option<String>,
+    word: String,
+    /// Looked up in the dictionary if omitted.
+    pinyin: Option<String>,
+}
+
+/// The games of the race players whose game is `over`, by player id and name.
+fn race_games(group_id: Option<&String>, over: Vec<&Player>) -> Vec<RaceGame> {
+    let now = SystemTime::now();
+    over.into_iter()
+        .map(|player| {
+            let game = GameRecord {
+                group_id: group_id.cloned(),
+                finished_at: stats::unix_time(now),
+                won: player.solved_in.is_some(),
+                attempts: player.attempts.len(),
+                duration_ms: player.solved_in.map(|time| time.as_millis() as u64),
+            };
+            let name = (player.name != player.id).then(|| player.name.clone());
+            (player.id.clone(), name, game)
+        })
+        .collect()
+}
+
+/// Records games taken out of a race by `race_games`.
+fn record_race(games: Vec<RaceGame>) {
+    for (player_id, name, game) in games {
+        if let Err(e) = stats::record(&player_id, name.as_deref(), game) {
+            eprintln!("failed to record a game for `{player_id}`: {e}");
+        }
+    }
+}
+
+fn race_attempt_inner(input: RaceAttemptInput) -> Result<String, OmniError> {
+    let mut board = race::with_race(&input.race_id, |race| {
+        if race.is_finished() {
+            Err(RaceError::Finished(input.race_id.clone()))?
+        }
+        let player = race
+            .player(&input.player_id)
+            .ok_or_else(|| RaceError::UnknownPlayer(input.player_id.clone()))?;
+        if player.is_done() {
+            Err(RaceError::PlayerDone(input.player_id.clone()))?
+        }
+
+        let mut board = race.input.clone();
+        board.attempts = player.attempts.clone();
+        Ok(board)
+    })?;
+    let previous = board.attempts.len();
+
+    let pinyin = match input.pinyin {
+        Some(pinyin) => pinyin,
+        None => {
+            let list = base_list(board.group_id.as_ref(), board.list.as_ref())?;
+            list.lookup(&list.normalize(&input.word, &dict::get().variants))
+                .map(|answer| answer.pinyin.clone())
+                .ok_or_else(|| RaceError::MissingPinyin(input.word.clone()))?
+        }
+    };
+    board.attempts.push(Attempt {
+        word: input.word,
+        pinyin,
+        verified: None,
+        guesser: None,
+    });
+
+    // only a guess that scores counts
+    let (length, scored) = score(&mut board, false)?;
+    let guess = board.attempts.last().cloned().unwrap();
+    let row = scored
+        .last()
+        .map(|(attempt, _)| attempt.characters.iter().map(|c| c.result.whole).collect())
+        .unwrap_or_default();
+
+    let (finished, won, race_finished, games) = race::with_race(&input.race_id, |race| {
+        if race.is_finished() {
+            Err(RaceError::Finished(input.race_id.clone()))?
+        }
+        let elapsed = race.elapsed();
+        let player = race
+            .player_mut(&input.player_id, input.name.as_deref())
+            .ok_or_else(|| RaceError::UnknownPlayer(input.player_id.clone()))?;
+        if player.attempts.len() != previous {
+            Err(RaceError::Conflict(input.player_id.clone()))?
+        }
+
+        if guess.word == board.answer.word {
+            player.solved_in = Some(elapsed);
+        }
+        player.attempts.push(guess);
+        player.rows.push(row);
+
+        let (finished, won) = (player.is_done(), player.solved_in.is_some());
+        let games = race_games(board.group_id.as_ref(), race.take_over());
+        Ok((finished, won, race.is_finished(), games))
+    })?;
+
+    record_race(games);
+
+    // the answer stays hidden until the race is over
+    let (answer_row, reveal) = reveal(&board, race_finished, won)?;
+    let output = Output {
+        finished: finished || race_finished,
+        result: display(&board, scored),
+        word_length: length,
+        max_attempt_count: MAX_ATTEMPT_COUNT,
+        solver: None,
+        answer_row,
+        reveal,
+    };
+
+    render::render(&output)
+}
+
+/// Adds a guess to the player's own board while the race is on, and renders
+/// that board.
+async fn race_attempt(Json(input): Json<RaceAttemptInput>) -> (StatusCode, Json<ImageResponse>) {
+    match race_attempt_inner(input) {
+        Ok(image_base64) => (
+            StatusCode::OK,
+            Json(ImageResponse::with_image("ok", image_base64)),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ImageResponse::new(format!("error: {}", e))),
+        ),
+    }
+}
+
+fn race_board_inner(race_id: &str, player_id: &str) -> Result<String, OmniError> {
+    let (mut board, finished, won, race_finished, games) = race::with_race(race_id, |race| {
+        let player = race
+            .player(player_id)
+            .ok_or_else(|| RaceError::UnknownPlayer(player_id.to_string()))?;
+
+        let mut board = race.input.clone();
+        board.attempts = player.attempts.clone();
+        let (finished, won) = (player.is_done(), player.solved_in.is_some());
+        let race_finished = race.is_finished();
+        let games = race_games(board.group_id.as_ref(), race.take_over());
+        Ok((board, finished, won, race_finished, games))
+    })?;
+    record_race(games);
+    let (length, scored) = score(&mut board, false)?;
+
+    let (answer_row, reveal) = reveal(&board, race_finished, won)?;
+    let output = Output {
+        result: display(&board, scored),
+        word_length: length,
+        max_attempt_count: MAX_ATTEMPT_COUNT,
+        finished: finished || race_finished,
+        solver: None,
+        answer_row,
+        reveal,
+    };
+
+    render::render(&output)
+}
+
+/// Renders one player's board.
+async fn race_board(
+    Query(params): Query<HashMap<String, String>>,
+) -> (StatusCode, Json<ImageResponse>) {
+    let race_id = params
+        .get("race_id")
+        .map(String::as_str)
+        .unwrap_or_default();
+    let player_id = params
+        .get("player_id")
+        .map(String::as_str)
+        .unwrap_or_default();
+
+    match race_board_inner(race_id, player_id) {
+        Ok(image_base64) => (
+            StatusCode::OK,
+            Json(ImageResponse::with_image("ok", image_base64)),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ImageResponse::new(format!("error: {}", e))),
+        ),
+    }
+}
+
+fn race_standings_inner(race_id: &str) -> Result<Standings, OmniError> {
+    let (standings, games) = race::with_race(race_id, |race| {
+        let mut standings = race.standings();
+        if let Some(answer) = &mut standings.answer {
+            answer.word = dict::get()
+                .variants
+                .convert(&answer.word, race.input.script);
+        }
+        let group_id = race.input.group_id.clone();
+        let games = race_games(group_id.as_ref(), race.take_over());
+        Ok((standings, games))
+    })?;
+    record_race(games);
+
+    Ok(standings)
+}
+
+/// Players ranked so far; the answer is included once the race is over.
+async fn race_standings(
+    Query(params): Query<HashMap<String, String>>,
+) -> (StatusCode, Json<DataResponse<Standings>>) {
+    let race_id = params
+        .get("race_id")
+        .map(String::as_str)
+        .unwrap_or_default();
+
+    match race_standings_inner(race_id) {
+        Ok(standings) => (
+            StatusCode::OK,
+            Json(DataResponse::with_data("ok", standings)),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(DataResponse::new(format!("error: {}", e))),
+        ),
+    }
+}
+
+async fn race_standings_image(
+    Query(params): Query<HashMap<String, String>>,
+) -> (StatusCode, Json<ImageResponse>) {
+    let race_id = params
+        .get("race_id")
+        .map(String::as_str)
+        .unwrap_or_default();
+
+    match race_standings_inner(race_id).and_then(|standings| render::render_standings(&standings)) {
+        Ok(image_base64) => (
+            StatusCode::OK,
+            Json(ImageResponse::with_image("ok", image_base64)),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ImageResponse::new(format!("error: {}", e))),
+        ),
+    }
+}
+
+/// A player's record over every finished game.
+async fn player_stats(
+    Query(params): Query<HashMap<String, String>>,
+) -> (StatusCode, Json<DataResponse<PlayerStats>>) {
+    let player_id = params
+        .get("player_id")
+        .map(String::as_str)
+        .unwrap_or_default();
+
+    match stats::get(player_id) {
+        Ok(stats) => (StatusCode::OK, Json(DataResponse::with_data("ok", stats))),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(DataResponse::new(format!("error: {}", e))),
+        ),
+    }
+}
+
+async fn player_stats_image(
+    Query(params): Query<HashMap<String, String>>,
+) -> (StatusCode, Json<ImageResponse>) {
+    let player_id = params
+        .get("player_id")
+        .map(String::as_str)
+        .unwrap_or_default();
+
+    match stats::get(player_id).and_then(|stats| render::render_stats(&stats)) {
+        Ok(image_base64) => (
+            StatusCode::OK,
+            Json(ImageResponse::with_image("ok", image_base64)),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ImageResponse::new(format!("error: {}", e))),
+        ),
+    }
+}
+
+async fn try_get_pinyin(Query(params): Query<HashMap<String, String>>) -> Json<Option<Answer>> {
     let word = match params.get("word") {
         Some(word) => word,
         None => return Json(None),
     };
 
-    Json(REVERSE_ANSWERS.get(word).map(|index| DICT[*index].clone()))
+    let answer = dict::get()
+        .list(params.get("list").map(String::as_str))
+        .ok()
+        .and_then(|list| {
+            list.lookup(&list.normalize(word, &dict::get().variants))
+                .cloned()
+        });
+
+    Json(answer.map(|answer| convert_answer(answer, script(&params))))
+}
+
+#[derive(Debug, Deserialize)]
+struct UploadListInput {
+    group_id: String,
+    /// The group's admin, or whoever becomes it with the group's first list.
+    admin_id: String,
+    name: String,
+    /// Dictionary list guesses are checked against, `dict::DEFAULT_LIST` if omitted.
+    base: Option<String>,
+    entries: Vec<CustomEntry>,
+}
+
+async fn upload_group_list(
+    Json(input): Json<UploadListInput>,
+) -> (StatusCode, Json<DataResponse<Vec<EntryError>>>) {
+    let base = input.base.as_deref().unwrap_or(dict::DEFAULT_LIST);
+    let res = group::build(base, input.entries)
+        .and_then(|list| group::upload(&input.group_id, &input.admin_id, &input.name, list));
+
+    match res {
+        Ok(()) => (StatusCode::OK, Json(DataResponse::new("ok"))),
+        Err(OmniError::Group(GroupError::InvalidEntries(errors))) => (
+            StatusCode::BAD_REQUEST,
+            Json(DataResponse::with_data(
+                format!("error: {} invalid entries", errors.len()),
+                errors,
+            )),
+        ),
+        Err(e @ OmniError::Group(GroupError::NotAdmin(_))) => (
+            StatusCode::FORBIDDEN,
+            Json(DataResponse::new(format!("error: {}", e))),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(DataResponse::new(format!("error: {}", e))),
+        ),
+    }
+}
+
+async fn group_lists(
+    Query(params): Query<HashMap<String, String>>,
+) -> (StatusCode, Json<DataResponse<Vec<CustomListSummary>>>) {
+    let group_id = params.get("group_id").map(String::as_str).unwrap_or_default();
+
+    match group::summaries(group_id) {
+        Ok(summaries) => (
+            StatusCode::OK,
+            Json(DataResponse::with_data("ok", summaries)),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(DataResponse::new(format!("error: {}", e))),
+        ),
+    }
+}
+
+async fn delete_group_list(
+    Query(params): Query<HashMap<String, String>>,
+) -> (StatusCode, Json<DataResponse<()>>) {
+    let group_id = params.get("group_id").map(String::as_str).unwrap_or_default();
+    let admin_id = params.get("admin_id").map(String::as_str).unwrap_or_default();
+    let name = params.get("name").map(String::as_str).unwrap_or_default();
+
+    match group::delete(group_id, admin_id, name) {
+        Ok(()) => (StatusCode::OK, Json(DataResponse::new("ok"))),
+        Err(e @ OmniError::Group(GroupError::NotAdmin(_))) => (
+            StatusCode::FORBIDDEN,
+            Json(DataResponse::new(format!("error: {}", e))),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(DataResponse::new(format!("error: {}", e))),
+        ),
+    }
+}
+
+/// Entries a leaderboard shows unless `limit` says otherwise.
+const DEFAULT_LEADERBOARD_LIMIT: usize = 10;
+
+#[derive(Debug, Deserialize)]
+struct LeaderboardParams {
+    group_id: String,
+    #[serde(default)]
+    period: Period,
+    #[serde(default)]
+    metric: Metric,
+    limit: Option<usize>,
+}
+
+fn leaderboard_inner(params: &LeaderboardParams) -> Result<Leaderboard, OmniError> {
+    stats::leaderboard(
+        &params.group_id,
+        params.period,
+        params.metric,
+        params.limit.unwrap_or(DEFAULT_LEADERBOARD_LIMIT),
+    )
+}
+
+async fn group_leaderboard(
+    Query(params): Query<LeaderboardParams>,
+) -> (StatusCode, Json<DataResponse<Leaderboard>>) {
+    match leaderboard_inner(&params) {
+        Ok(leaderboard) => (
+            StatusCode::OK,
+            Json(DataResponse::with_data("ok", leaderboard)),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(DataResponse::new(format!("error: {}", e))),
+        ),
+    }
+}
+
+async fn group_leaderboard_image(
+    Query(params): Query<LeaderboardParams>,
+) -> (StatusCode, Json<ImageResponse>) {
+    match leaderboard_inner(&params)
+        .and_then(|leaderboard| render::render_leaderboard(&leaderboard))
+    {
+        Ok(image_base64) => (
+            StatusCode::OK,
+            Json(ImageResponse::with_image("ok", image_base64)),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ImageResponse::new(format!("error: {}", e))),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::process;
+
+    /// Removes the stats of the player when dropped.
+    struct TestPlayer(String);
+
+    impl Drop for TestPlayer {
+        fn drop(&mut self) {
+            let players = dict::data_dir().join("players");
+            let _ = fs::remove_file(players.join(format!("{}.json", self.0)));
+            let _ = fs::remove_dir(&players);
+            let _ = fs::remove_dir(dict::data_dir());
+        }
+    }
+
+    fn board(player: Option<&str>, guesser: Option<&str>, won: bool) -> Input {
+        let guesser = guesser.map(|id| serde_json::json!({ "id": id }));
+        let last = if won { "一心一意" } else { "三心二意" };
+        serde_json::from_value(serde_json::json!({
+            "answer": { "word": "一心一意", "pinyin": "yi1 xin1 yi1 yi4" },
+            "attempts": [
+                { "word": "三心二意", "pinyin": "san1 xin1 er4 yi4", "guesser": guesser },
+                { "word": last, "pinyin": "yi1 xin1 yi1 yi4", "guesser": guesser },
+            ],
+            "player": player.map(|id| serde_json::json!({ "id": id })),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn solo_game_recorded_for_player() {
+        let player = TestPlayer(format!("test-{}-solo", process::id()));
+
+        let now = SystemTime::now();
+        record_players(&board(Some(&player.0), None, true), true, None, now);
+        record_players(&board(Some(&player.0), None, false), false, None, now);
+
+        let stats = stats::get(&player.0).unwrap();
+        assert_eq!((stats.played, stats.wins), (2, 1));
+        assert_eq!(stats.distribution[1], 1);
+    }
+
+    #[test]
+    fn shared_board_credits_guessers() {
+        let player = TestPlayer(format!("test-{}-host", process::id()));
+        let guesser = TestPlayer(format!("test-{}-guesser", process::id()));
+
+        let input = board(Some(&player.0), Some(&guesser.0), false);
+        record_players(&input, false, None, SystemTime::now());
+
+        assert!(stats::get(&player.0).is_err());
+        assert_eq!(stats::get(&guesser.0).unwrap().played, 1);
+    }
 }
@@ -0,0 +1,127 @@
+//! Mandarin tone sandhi. Word lists store citation tones (`yi1`, `bu4`), but
+//! players often type the tone they pronounce (`yi2 ding4`). With sandhi
+//! scoring on, both the answer and the guess are normalized to their spoken
+//! tones before the tones are compared.
+
+use crate::model::{CharInfo, MandarinTone, Tone};
+use serde::Deserialize;
+
+/// Which tone changes are applied before tones are compared.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Sandhi {
+    /// Compare citation tones as typed.
+    #[default]
+    Off,
+    /// 一 and 不 only.
+    Yibu,
+    /// 一, 不 and third-tone sandhi (`ni3 hao3` is spoken `ni2 hao3`).
+    Full,
+}
+
+/// Tone shown on the board when sandhi scoring is on.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ToneDisplay {
+    /// The tone the word list gives.
+    #[default]
+    Citation,
+    /// The tone after sandhi.
+    Spoken,
+}
+
+/// Spoken tone of each syllable. The tones of 一 and 不 are derived from the
+/// following syllable alone, so whichever of their tones was typed ends up the
+/// same.
+pub fn spoken_tones(characters: &[impl CharInfo], sandhi: Sandhi) -> Vec<Tone> {
+    let tones = characters.iter().map(CharInfo::tone).collect::<Vec<_>>();
+    if sandhi == Sandhi::Off {
+        return tones;
+    }
+
+    characters
+        .iter()
+        .enumerate()
+        .map(|(i, character)| {
+            let next = tones.get(i + 1).and_then(|tone| tone.mandarin());
+            let spoken = match (
+                character.literal().as_str(),
+                character.pronunciation().as_str(),
+            ) {
+                ("一", "yi") => match next {
+                    Some(MandarinTone::Falling) => MandarinTone::Rising,
+                    Some(MandarinTone::High | MandarinTone::Rising | MandarinTone::Low) => {
+                        MandarinTone::Falling
+                    }
+                    _ => MandarinTone::High,
+                },
+                ("不", "bu") => match next {
+                    Some(MandarinTone::Falling) => MandarinTone::Rising,
+                    _ => MandarinTone::Falling,
+                },
+                _ if sandhi == Sandhi::Full
+                    && tones[i].mandarin() == Some(MandarinTone::Low)
+                    && next == Some(MandarinTone::Low) =>
+                {
+                    MandarinTone::Rising
+                }
+                _ => return tones[i],
+            };
+
+            spoken.into()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Attempt, Scheme};
+
+    fn spoken(word: &str, pinyin: &str, sandhi: Sandhi) -> String {
+        let attempt = Attempt {
+            word: word.to_string(),
+            pinyin: pinyin.to_string(),
+            verified: None,
+            guesser: None,
+        };
+        let characters = attempt
+            .to_characters(word.chars().count(), Scheme::Pinyin)
+            .unwrap();
+        spoken_tones(&characters, sandhi)
+            .iter()
+            .map(Tone::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn yi_and_bu() {
+        assert_eq!(spoken("一定", "yi1 ding4", Sandhi::Yibu), "24");
+        assert_eq!(spoken("一天", "yi1 tian1", Sandhi::Yibu), "41");
+        assert_eq!(spoken("不要", "bu4 yao4", Sandhi::Yibu), "24");
+        assert_eq!(spoken("不好", "bu2 hao3", Sandhi::Yibu), "43");
+    }
+
+    #[test]
+    fn third_tone_only_when_full() {
+        assert_eq!(spoken("你好", "ni3 hao3", Sandhi::Yibu), "33");
+        assert_eq!(spoken("你好", "ni3 hao3", Sandhi::Full), "23");
+        assert_eq!(spoken("你好", "ni3 hao3", Sandhi::Off), "33");
+    }
+
+    #[test]
+    fn cantonese_tones_untouched() {
+        let attempt = Attempt {
+            word: "一定".to_string(),
+            pinyin: "jat1 ding6".to_string(),
+            verified: None,
+            guesser: None,
+        };
+        let characters = attempt.to_characters(2, Scheme::Jyutping).unwrap();
+        let tones = spoken_tones(&characters, Sandhi::Full);
+        assert_eq!(
+            tones,
+            [characters[0].pinyin.tone, characters[1].pinyin.tone]
+        );
+    }
+}
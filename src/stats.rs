@@ -0,0 +1,495 @@
+//! Per-player records of finished games, stored as one file per player under
+//! `data/players/`, and group leaderboards over them.
+//!
+//! Boards sent to `/attempt` are kept by the client and may be sent again, so
+//! a game is only recorded when it finishes while open: from `/start`, or else
+//! its first unfinished board, until its end.
+
+use crate::constant::MAX_ATTEMPT_COUNT;
+use crate::dict::{self, data_dir};
+use crate::error::{OmniError, StatsError};
+use crate::group::is_valid_id;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::{Mutex, Once, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const PLAYERS_DIR: &str = "players";
+
+/// Set once every record on disk has been read.
+static LOADED: Once = Once::new();
+
+/// Open games older than this are forgotten.
+const MAX_GAME_TIME: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// One finished game of a player.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GameRecord {
+    /// Group the game was played in, none for private games.
+    pub group_id: Option<String>,
+    /// Unix time in seconds.
+    pub finished_at: u64,
+    pub won: bool,
+    pub attempts: usize,
+    /// Time from the start of the game to the guess that found the answer.
+    pub duration_ms: Option<u64>,
+}
+
+/// Everything stored about a player.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct PlayerRecord {
+    pub name: Option<String>,
+    /// Oldest first.
+    pub games: Vec<GameRecord>,
+}
+
+/// A player's record, summed up.
+#[derive(Debug, Serialize)]
+pub struct PlayerStats {
+    pub id: String,
+    pub name: Option<String>,
+    pub played: usize,
+    pub wins: usize,
+    /// Wins over games played, 0 without games.
+    pub win_rate: f64,
+    /// Wins since the last loss.
+    pub current_streak: usize,
+    pub best_streak: usize,
+    /// Wins by attempt count, the first entry being wins in one attempt.
+    pub distribution: Vec<usize>,
+}
+
+/// Time window of a leaderboard, ending now.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    /// The last 24 hours.
+    Day,
+    /// The last 7 days.
+    Week,
+    /// The last 30 days.
+    Month,
+    #[default]
+    All,
+}
+
+impl Period {
+    /// Unix time the window starts at.
+    fn since(self, now: u64) -> u64 {
+        let days = match self {
+            Period::Day => 1,
+            Period::Week => 7,
+            Period::Month => 30,
+            Period::All => return 0,
+        };
+        now.saturating_sub(days * 24 * 60 * 60)
+    }
+}
+
+/// What a leaderboard is ranked by.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    #[default]
+    Wins,
+    /// Fewest attempts per win.
+    AverageAttempts,
+    /// Shortest time to a win.
+    Fastest,
+    LongestStreak,
+}
+
+/// A player's results in a group over a period.
+#[derive(Debug, Serialize)]
+pub struct LeaderboardEntry {
+    /// 1-based.
+    pub rank: usize,
+    pub id: String,
+    pub name: Option<String>,
+    pub played: usize,
+    pub wins: usize,
+    /// Over won games only.
+    pub average_attempts: Option<f64>,
+    /// Shortest time to a win.
+    pub fastest_ms: Option<u64>,
+    /// Most wins in a row within the period.
+    pub longest_streak: usize,
+}
+
+/// The ranked players of a group.
+#[derive(Debug, Serialize)]
+pub struct Leaderboard {
+    pub group_id: String,
+    pub period: Period,
+    pub metric: Metric,
+    /// Best first.
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+lazy_static! {
+    /// Records of the players read so far, as they are on disk.
+    static ref PLAYERS: RwLock<HashMap<String, PlayerRecord>> = RwLock::new(HashMap::new());
+    /// Held while a record is changed, so that concurrent changes are not lost.
+    static ref WRITE: Mutex<()> = Mutex::new(());
+    static ref OPEN_GAMES: Mutex<HashMap<String, SystemTime>> = Mutex::new(HashMap::new());
+}
+
+/// `time` in whole seconds since the Unix epoch.
+pub fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Marks `game_id` as being played, keeping its start time if already open.
+pub fn open(game_id: &str) {
+    let mut games = OPEN_GAMES.lock().unwrap();
+    games.retain(|_, started| started.elapsed().unwrap_or_default() < MAX_GAME_TIME);
+    games
+        .entry(game_id.to_string())
+        .or_insert_with(SystemTime::now);
+}
+
+/// Ends `game_id`, returning when it started if it was open.
+pub fn close(game_id: &str) -> Option<SystemTime> {
+    OPEN_GAMES.lock().unwrap().remove(game_id)
+}
+
+fn player_path(player_id: &str) -> Result<PathBuf, StatsError> {
+    if !is_valid_id(player_id) {
+        return Err(StatsError::InvalidPlayerId(player_id.to_string()));
+    }
+
+    Ok(data_dir()
+        .join(PLAYERS_DIR)
+        .join(format!("{player_id}.json")))
+}
+
+/// Runs `f` on the record of a player, read from the cache or else from disk.
+/// A player without a file has an empty record, which is not cached.
+fn read_player<T>(player_id: &str, f: impl FnOnce(&PlayerRecord) -> T) -> Result<T, OmniError> {
+    let path = player_path(player_id)?;
+    if let Some(record) = PLAYERS.read().unwrap().get(player_id) {
+        return Ok(f(record));
+    }
+
+    let record: PlayerRecord = match fs::read_to_string(&path) {
+        Ok(data) => serde_json::from_str(&data)?,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(f(&PlayerRecord::default())),
+        Err(e) => Err(e)?,
+    };
+    let res = f(&record);
+    // a change made meanwhile is newer than what was read
+    PLAYERS
+        .write()
+        .unwrap()
+        .entry(player_id.to_string())
+        .or_insert(record);
+
+    Ok(res)
+}
+
+/// Runs `f` on a copy of the record of a player and saves the result, first to
+/// disk and then to the cache.
+fn update_player<T>(
+    player_id: &str,
+    f: impl FnOnce(&mut PlayerRecord) -> T,
+) -> Result<T, OmniError> {
+    let path = player_path(player_id)?;
+    let _write = WRITE.lock().unwrap();
+
+    let mut record = read_player(player_id, PlayerRecord::clone)?;
+    let res = f(&mut record);
+
+    fs::create_dir_all(data_dir().join(PLAYERS_DIR))?;
+    dict::write_file(&path, &serde_json::to_string_pretty(&record)?)?;
+    PLAYERS
+        .write()
+        .unwrap()
+        .insert(player_id.to_string(), record);
+
+    Ok(res)
+}
+
+/// Adds a finished game to a player's record, updating their name if given.
+pub fn record(player_id: &str, name: Option<&str>, game: GameRecord) -> Result<(), OmniError> {
+    update_player(player_id, |record| {
+        if name.is_some() {
+            record.name = name.map(str::to_string);
+        }
+        record.games.push(game);
+    })
+}
+
+impl PlayerRecord {
+    /// The stats of this record, for the player `id`.
+    pub fn stats(&self, id: &str) -> PlayerStats {
+        let mut distribution = vec![0; MAX_ATTEMPT_COUNT];
+        let (mut streak, mut best_streak) = (0, 0);
+        for game in &self.games {
+            if game.won {
+                streak += 1;
+                best_streak = best_streak.max(streak);
+                if let Some(count) = game
+                    .attempts
+                    .checked_sub(1)
+                    .and_then(|i| distribution.get_mut(i))
+                {
+                    *count += 1;
+                }
+            } else {
+                streak = 0;
+            }
+        }
+
+        let played = self.games.len();
+        let wins = self.games.iter().filter(|game| game.won).count();
+        PlayerStats {
+            id: id.to_string(),
+            name: self.name.clone(),
+            played,
+            wins,
+            win_rate: if played == 0 {
+                0.0
+            } else {
+                wins as f64 / played as f64
+            },
+            current_streak: streak,
+            best_streak,
+            distribution,
+        }
+    }
+}
+
+impl PlayerRecord {
+    /// The player's results in `group_id` since `since`, none if they have not
+    /// played there.
+    fn entry(&self, id: &str, group_id: &str, since: u64) -> Option<LeaderboardEntry> {
+        let games = self
+            .games
+            .iter()
+            .filter(|game| game.group_id.as_deref() == Some(group_id) && game.finished_at >= since)
+            .collect::<Vec<_>>();
+        if games.is_empty() {
+            return None;
+        }
+
+        let won = games.iter().filter(|game| game.won).collect::<Vec<_>>();
+        let (mut streak, mut longest_streak) = (0, 0);
+        for game in &games {
+            streak = if game.won { streak + 1 } else { 0 };
+            longest_streak = longest_streak.max(streak);
+        }
+
+        Some(LeaderboardEntry {
+            rank: 0,
+            id: id.to_string(),
+            name: self.name.clone(),
+            played: games.len(),
+            wins: won.len(),
+            average_attempts: (!won.is_empty()).then(|| {
+                won.iter().map(|game| game.attempts).sum::<usize>() as f64 / won.len() as f64
+            }),
+            fastest_ms: won.iter().filter_map(|game| game.duration_ms).min(),
+            longest_streak,
+        })
+    }
+}
+
+/// Reads the record of every player not cached yet, once. Records saved
+/// later go through the cache, which therefore stays complete. Files that
+/// cannot be read are skipped.
+fn load_all() {
+    LOADED.call_once(|| {
+        let Ok(dir) = fs::read_dir(data_dir().join(PLAYERS_DIR)) else {
+            return;
+        };
+
+        for path in dir.flatten().map(|entry| entry.path()) {
+            let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+
+            if let Err(e) = read_player(id, |_| ()) {
+                eprintln!("skipping the record of `{id}`: {e}");
+            }
+        }
+    });
+}
+
+/// Ranks the players of a group by `metric` over `period`, keeping the first
+/// `limit`. Ties go to the player with more wins.
+pub fn leaderboard(
+    group_id: &str,
+    period: Period,
+    metric: Metric,
+    limit: usize,
+) -> Result<Leaderboard, OmniError> {
+    load_all();
+    let players = PLAYERS.read().unwrap();
+
+    let since = period.since(unix_time(SystemTime::now()));
+    let mut entries = players
+        .iter()
+        .filter_map(|(id, record)| record.entry(id, group_id, since))
+        .collect::<Vec<_>>();
+
+    // players without a value for the metric go last
+    let by_wins = |a: &LeaderboardEntry, b: &LeaderboardEntry| {
+        b.wins.cmp(&a.wins).then_with(|| a.id.cmp(&b.id))
+    };
+    entries.sort_by(|a, b| {
+        match metric {
+            Metric::Wins => Ordering::Equal,
+            Metric::AverageAttempts => match (a.average_attempts, b.average_attempts) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            },
+            Metric::Fastest => match (a.fastest_ms, b.fastest_ms) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            },
+            Metric::LongestStreak => b.longest_streak.cmp(&a.longest_streak),
+        }
+        .then_with(|| by_wins(a, b))
+    });
+
+    entries.truncate(limit);
+    for (i, entry) in entries.iter_mut().enumerate() {
+        entry.rank = i + 1;
+    }
+
+    Ok(Leaderboard {
+        group_id: group_id.to_string(),
+        period,
+        metric,
+        entries,
+    })
+}
+
+/// The stats of a player who has finished at least one game.
+pub fn get(player_id: &str) -> Result<PlayerStats, OmniError> {
+    let stats = read_player(player_id, |record| {
+        if record.games.is_empty() {
+            Err(StatsError::UnknownPlayer(player_id.to_string()))
+        } else {
+            Ok(record.stats(player_id))
+        }
+    })??;
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    /// Removes the player's file when dropped, and the directories above it up
+    /// to the test data directory if they are left empty.
+    struct TestPlayer(String);
+
+    impl TestPlayer {
+        fn new(name: &str) -> Self {
+            Self(format!("test-{}-{name}", process::id()))
+        }
+    }
+
+    impl Drop for TestPlayer {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(player_path(&self.0).unwrap());
+            let _ = fs::remove_dir(data_dir().join(PLAYERS_DIR));
+            let _ = fs::remove_dir(data_dir());
+        }
+    }
+
+    fn game(won: bool, attempts: usize) -> GameRecord {
+        GameRecord {
+            group_id: None,
+            finished_at: 0,
+            won,
+            attempts,
+            duration_ms: None,
+        }
+    }
+
+    #[test]
+    fn streaks_and_distribution() {
+        let record = PlayerRecord {
+            name: None,
+            games: vec![game(true, 2), game(true, 3), game(false, 10), game(true, 2)],
+        };
+        let stats = record.stats("p");
+
+        assert_eq!((stats.played, stats.wins), (4, 3));
+        assert_eq!((stats.current_streak, stats.best_streak), (1, 2));
+        assert_eq!(&stats.distribution[..3], &[0, 2, 1]);
+    }
+
+    #[test]
+    fn recorded_games_are_saved() {
+        let player = TestPlayer::new("saved");
+        record(&player.0, Some("Ann"), game(true, 4)).unwrap();
+        record(&player.0, None, game(false, 10)).unwrap();
+
+        PLAYERS.write().unwrap().remove(&player.0);
+        let stats = get(&player.0).unwrap();
+        assert_eq!(stats.name.as_deref(), Some("Ann"));
+        assert_eq!((stats.played, stats.wins), (2, 1));
+    }
+
+    #[test]
+    fn missing_player_not_cached() {
+        let player = TestPlayer::new("missing");
+        assert!(get(&player.0).is_err());
+        assert!(!PLAYERS.read().unwrap().contains_key(&player.0));
+    }
+
+    #[test]
+    fn unreadable_record_not_overwritten() {
+        let player = TestPlayer::new("unreadable");
+        fs::create_dir_all(data_dir().join(PLAYERS_DIR)).unwrap();
+        fs::write(player_path(&player.0).unwrap(), "{").unwrap();
+
+        assert!(record(&player.0, None, game(true, 1)).is_err());
+        assert_eq!(
+            fs::read_to_string(player_path(&player.0).unwrap()).unwrap(),
+            "{"
+        );
+    }
+
+    #[test]
+    fn leaderboard_skips_unreadable_records() {
+        let (ann, bob, broken) = (
+            TestPlayer::new("ann"),
+            TestPlayer::new("bob"),
+            TestPlayer::new("broken"),
+        );
+        let group_id = format!("test-{}-group", process::id());
+        let in_group = |won, attempts| GameRecord {
+            group_id: Some(group_id.clone()),
+            finished_at: unix_time(SystemTime::now()),
+            ..game(won, attempts)
+        };
+        record(&ann.0, None, in_group(true, 5)).unwrap();
+        record(&bob.0, None, in_group(true, 2)).unwrap();
+        record(&bob.0, None, in_group(false, 10)).unwrap();
+        fs::write(player_path(&broken.0).unwrap(), "{").unwrap();
+
+        let board = leaderboard(&group_id, Period::Day, Metric::AverageAttempts, 10).unwrap();
+        let ids = board
+            .entries
+            .iter()
+            .map(|e| e.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, [bob.0.as_str(), ann.0.as_str()]);
+    }
+}
@@ -0,0 +1,108 @@
+//! Simplified ↔ traditional character mapping, read from an OpenCC
+//! `STCharacters.txt` style table. Each line is a simplified character, a tab,
+//! and its space-separated traditional forms with the preferred one first,
+//! e.g. `发\t發 髮`.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// File under `data/` holding the table.
+pub const VARIANTS_FILE: &str = "STCharacters.txt";
+
+/// Script a board is shown in.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Script {
+    /// As the word lists are written.
+    #[default]
+    Simplified,
+    /// Each character in its preferred traditional form.
+    Traditional,
+}
+
+/// Character mappings both ways.
+#[derive(Debug, Default)]
+pub struct VariantTable {
+    to_traditional: HashMap<char, char>,
+    to_simplified: HashMap<char, char>,
+}
+
+impl VariantTable {
+    /// Reads a table, skipping lines that do not map a single character.
+    pub fn parse(src: &str) -> Self {
+        let mut table = Self::default();
+
+        for line in src.lines() {
+            let Some((simplified, traditional)) = line.split_once('\t') else {
+                continue;
+            };
+            let mut simplified = simplified.chars();
+            let (Some(simplified), None) = (simplified.next(), simplified.next()) else {
+                continue;
+            };
+
+            for (i, traditional) in traditional
+                .split_whitespace()
+                .filter_map(|t| t.chars().next())
+                .enumerate()
+            {
+                if i == 0 {
+                    table.to_traditional.insert(simplified, traditional);
+                }
+                table.to_simplified.entry(traditional).or_insert(simplified);
+            }
+        }
+
+        table
+    }
+
+    /// Maps every traditional character of `text` to its simplified form.
+    pub fn simplify(&self, text: &str) -> String {
+        text.chars()
+            .map(|c| self.to_simplified.get(&c).copied().unwrap_or(c))
+            .collect()
+    }
+
+    /// Maps every simplified character of `text` to its preferred traditional
+    /// form.
+    pub fn traditionalize(&self, text: &str) -> String {
+        text.chars()
+            .map(|c| self.to_traditional.get(&c).copied().unwrap_or(c))
+            .collect()
+    }
+
+    /// `text`, written in simplified characters, in `script`.
+    pub fn convert(&self, text: &str, script: Script) -> String {
+        match script {
+            Script::Simplified => text.to_string(),
+            Script::Traditional => self.traditionalize(text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE: &str = "干\t幹 乾 榦\n后\t後\n着\t著\n发\t發 髮\n";
+
+    #[test]
+    fn preferred_traditional_form() {
+        let table = VariantTable::parse(TABLE);
+        assert_eq!(table.traditionalize("头发"), "头發");
+        assert_eq!(table.convert("后来", Script::Traditional), "後来");
+        assert_eq!(table.convert("后来", Script::Simplified), "后来");
+    }
+
+    #[test]
+    fn every_traditional_form_simplifies() {
+        let table = VariantTable::parse(TABLE);
+        assert_eq!(table.simplify("乾幹髮後"), "干干发后");
+    }
+
+    #[test]
+    fn malformed_lines_skipped() {
+        let table = VariantTable::parse("干幹\n后后\t後\n\t發\n");
+        assert_eq!(table.simplify("幹後發"), "幹後發");
+    }
+}
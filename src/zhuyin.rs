@@ -0,0 +1,208 @@
+//! Zhuyin (Bopomofo) ↔ numbered pinyin conversion. Attempts may be typed in
+//! Zhuyin and are converted to pinyin before parsing; boards can be annotated
+//! with Zhuyin instead of pinyin.
+
+use crate::model::{MandarinTone, Pinyin, ToneExplicit};
+use serde::Serialize;
+
+const INITIALS: &[(&str, &str)] = &[
+    ("ㄅ", "b"),
+    ("ㄆ", "p"),
+    ("ㄇ", "m"),
+    ("ㄈ", "f"),
+    ("ㄉ", "d"),
+    ("ㄊ", "t"),
+    ("ㄋ", "n"),
+    ("ㄌ", "l"),
+    ("ㄍ", "g"),
+    ("ㄎ", "k"),
+    ("ㄏ", "h"),
+    ("ㄐ", "j"),
+    ("ㄑ", "q"),
+    ("ㄒ", "x"),
+    ("ㄓ", "zh"),
+    ("ㄔ", "ch"),
+    ("ㄕ", "sh"),
+    ("ㄖ", "r"),
+    ("ㄗ", "z"),
+    ("ㄘ", "c"),
+    ("ㄙ", "s"),
+];
+
+/// Zhuyin final, pinyin final after an initial, pinyin syllable on its own.
+#[rustfmt::skip]
+const FINALS: &[(&str, &str, &str)] = &[
+    ("ㄚ", "a", "a"), ("ㄛ", "o", "o"), ("ㄜ", "e", "e"), ("ㄝ", "ê", "ê"),
+    ("ㄞ", "ai", "ai"), ("ㄟ", "ei", "ei"), ("ㄠ", "ao", "ao"), ("ㄡ", "ou", "ou"),
+    ("ㄢ", "an", "an"), ("ㄣ", "en", "en"), ("ㄤ", "ang", "ang"), ("ㄥ", "eng", "eng"),
+    ("ㄦ", "er", "er"),
+    ("ㄧ", "i", "yi"), ("ㄧㄚ", "ia", "ya"), ("ㄧㄛ", "io", "yo"), ("ㄧㄝ", "ie", "ye"),
+    ("ㄧㄞ", "iai", "yai"), ("ㄧㄠ", "iao", "yao"), ("ㄧㄡ", "iu", "you"),
+    ("ㄧㄢ", "ian", "yan"), ("ㄧㄣ", "in", "yin"), ("ㄧㄤ", "iang", "yang"),
+    ("ㄧㄥ", "ing", "ying"),
+    ("ㄨ", "u", "wu"), ("ㄨㄚ", "ua", "wa"), ("ㄨㄛ", "uo", "wo"), ("ㄨㄞ", "uai", "wai"),
+    ("ㄨㄟ", "ui", "wei"), ("ㄨㄢ", "uan", "wan"), ("ㄨㄣ", "un", "wen"),
+    ("ㄨㄤ", "uang", "wang"), ("ㄨㄥ", "ong", "weng"),
+    ("ㄩ", "ü", "yu"), ("ㄩㄝ", "üe", "yue"), ("ㄩㄢ", "üan", "yuan"), ("ㄩㄣ", "ün", "yun"),
+    ("ㄩㄥ", "iong", "yong"),
+];
+
+/// Initials that stand alone as `zhi`, `chi`, ... with an empty Zhuyin final.
+const SIBILANTS: &[&str] = &["zh", "ch", "sh", "r", "z", "c", "s"];
+
+/// Initials after which `ü` is written `u`.
+const PALATALS: &[&str] = &["j", "q", "x"];
+
+const TONE_MARKS: &[(char, &str)] = &[('ˉ', "1"), ('ˊ', "2"), ('ˇ', "3"), ('ˋ', "4"), ('˙', "")];
+
+/// A syllable in Zhuyin, split the way the board shows it.
+#[derive(Clone, Debug, Serialize)]
+pub struct Zhuyin {
+    /// Symbol of the initial, empty if there is none.
+    pub initial: String,
+    /// Symbols of the final.
+    pub vowel: String,
+    /// Tone mark, empty for the first tone.
+    pub tone: String,
+}
+
+/// Whether `value` contains any Bopomofo symbol.
+pub fn is_zhuyin(value: &str) -> bool {
+    value.chars().any(|c| ('\u{3105}'..='\u{312F}').contains(&c))
+}
+
+/// Converts one Zhuyin syllable (`ㄉㄧㄥˋ`) to numbered pinyin (`ding4`). An
+/// unmarked syllable is first tone, `˙` marks the neutral tone.
+pub fn to_pinyin(value: &str) -> Option<String> {
+    let mut tone = "1";
+    let mut body = String::new();
+    for c in value.chars() {
+        match TONE_MARKS.iter().find(|(mark, _)| *mark == c) {
+            Some((_, num)) => tone = num,
+            None => body.push(c),
+        }
+    }
+
+    let initial = INITIALS
+        .iter()
+        .find(|(zhuyin, _)| body.starts_with(zhuyin))
+        .map(|(zhuyin, pinyin)| (zhuyin.len(), *pinyin));
+
+    let syllable = match initial {
+        Some((len, initial)) => {
+            let rest = &body[len..];
+            let vowel = if rest.is_empty() && SIBILANTS.contains(&initial) {
+                "i".to_string()
+            } else {
+                let (_, vowel, _) = FINALS.iter().find(|(zhuyin, _, _)| *zhuyin == rest)?;
+                if PALATALS.contains(&initial) {
+                    vowel.replace('ü', "u")
+                } else {
+                    vowel.to_string()
+                }
+            };
+            format!("{initial}{vowel}")
+        }
+        None => {
+            let (_, _, syllable) = FINALS.iter().find(|(zhuyin, _, _)| *zhuyin == body)?;
+            syllable.to_string()
+        }
+    };
+
+    Some(format!("{syllable}{tone}"))
+}
+
+/// Annotates a parsed syllable with Zhuyin. `y` and `w` are not initials in
+/// Zhuyin, so such syllables come back with an empty initial.
+pub fn from_pinyin(pinyin: &Pinyin) -> Option<Zhuyin> {
+    let initial = pinyin
+        .initial
+        .0
+        .as_deref()
+        .and_then(|initial| INITIALS.iter().find(|(_, pinyin)| *pinyin == initial));
+
+    let (initial, vowel) = match initial {
+        Some((zhuyin, initial)) => {
+            let vowel = if SIBILANTS.contains(initial) && pinyin.vowel == "i" {
+                ""
+            } else {
+                let vowel = match pinyin.vowel.strip_prefix('u') {
+                    Some(rest) if PALATALS.contains(initial) => format!("ü{rest}"),
+                    _ => pinyin.vowel.clone(),
+                };
+                let (zhuyin, _, _) = FINALS.iter().find(|(_, pinyin, _)| *pinyin == vowel)?;
+                zhuyin
+            };
+            (zhuyin.to_string(), vowel.to_string())
+        }
+        None => {
+            let (zhuyin, _, _) = FINALS
+                .iter()
+                .find(|(_, _, syllable)| *syllable == pinyin.pronunciation)?;
+            (String::new(), zhuyin.to_string())
+        }
+    };
+
+    let tone = match pinyin.tone.0 {
+        Some(ToneExplicit::Mandarin(tone)) => match tone {
+            MandarinTone::High => "",
+            MandarinTone::Rising => "ˊ",
+            MandarinTone::Low => "ˇ",
+            MandarinTone::Falling => "ˋ",
+            MandarinTone::Neutral => "˙",
+        },
+        None => "˙",
+        // Cantonese tones have no Zhuyin marks
+        Some(ToneExplicit::Cantonese(_)) => return None,
+    };
+
+    Some(Zhuyin {
+        initial,
+        vowel,
+        tone: tone.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Scheme;
+
+    fn annotate(pinyin: &str, scheme: Scheme) -> Option<(String, String, String)> {
+        let zhuyin = from_pinyin(&Pinyin::parse(pinyin, scheme).unwrap())?;
+        Some((zhuyin.initial, zhuyin.vowel, zhuyin.tone))
+    }
+
+    #[test]
+    fn zhuyin_to_pinyin() {
+        assert_eq!(to_pinyin("ㄉㄧㄥˋ").as_deref(), Some("ding4"));
+        assert_eq!(to_pinyin("ㄐㄩㄝˊ").as_deref(), Some("jue2"));
+        assert_eq!(to_pinyin("ㄓ").as_deref(), Some("zhi1"));
+        assert_eq!(to_pinyin("ㄧ").as_deref(), Some("yi1"));
+        assert_eq!(to_pinyin("ㄇㄚ˙").as_deref(), Some("ma"));
+        assert_eq!(to_pinyin("ㄅㄅ"), None);
+    }
+
+    #[test]
+    fn pinyin_to_zhuyin() {
+        let zhuyin = |initial: &str, vowel: &str, tone: &str| {
+            Some((initial.to_string(), vowel.to_string(), tone.to_string()))
+        };
+
+        assert_eq!(annotate("jue2", Scheme::Pinyin), zhuyin("ㄐ", "ㄩㄝ", "ˊ"));
+        assert_eq!(annotate("zhi1", Scheme::Pinyin), zhuyin("ㄓ", "", ""));
+        assert_eq!(annotate("yi1", Scheme::Pinyin), zhuyin("", "ㄧ", ""));
+        assert_eq!(annotate("ma", Scheme::Pinyin), zhuyin("ㄇ", "ㄚ", "˙"));
+    }
+
+    #[test]
+    fn cantonese_not_annotated() {
+        assert_eq!(annotate("gin1", Scheme::Jyutping), None);
+    }
+
+    #[test]
+    fn zhuyin_detected() {
+        assert!(is_zhuyin("ㄉㄧㄥˋ"));
+        assert!(!is_zhuyin("ding4"));
+    }
+}